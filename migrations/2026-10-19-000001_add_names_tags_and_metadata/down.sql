@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS rovers_plateau_id_name;
+ALTER TABLE rovers DROP COLUMN metadata;
+ALTER TABLE rovers DROP COLUMN tags;
+ALTER TABLE rovers DROP COLUMN name;
+
+DROP INDEX IF EXISTS plateaus_name;
+ALTER TABLE plateaus DROP COLUMN metadata;
+ALTER TABLE plateaus DROP COLUMN tags;
+ALTER TABLE plateaus DROP COLUMN name;
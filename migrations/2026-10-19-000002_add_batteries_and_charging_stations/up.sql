@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE plateaus ADD COLUMN charging_stations TEXT NOT NULL DEFAULT '[]';
+
+ALTER TABLE rovers ADD COLUMN battery_level INTEGER;
+ALTER TABLE rovers ADD COLUMN battery_capacity INTEGER;
+ALTER TABLE rovers ADD COLUMN turn_cost INTEGER;
+ALTER TABLE rovers ADD COLUMN move_cost INTEGER;
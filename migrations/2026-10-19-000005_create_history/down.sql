@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX history_plateau_id;
+
+DROP TABLE history;
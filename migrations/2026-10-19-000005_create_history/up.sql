@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    plateau_id VARCHAR NOT NULL,
+    rover_id VARCHAR NOT NULL,
+    batch VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    command TEXT NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    facing TEXT CHECK(facing IN ('north', 'east', 'south', 'west')) NOT NULL,
+    poses TEXT NOT NULL DEFAULT '[]',
+    FOREIGN KEY(plateau_id) REFERENCES plateaus(id)
+);
+
+CREATE INDEX history_plateau_id ON history (plateau_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS rovers;
+DROP TABLE IF EXISTS plateaus;
+
+DROP TYPE IF EXISTS direction;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TYPE direction AS ENUM ('north', 'east', 'south', 'west');
+
+CREATE TABLE IF NOT EXISTS plateaus (
+    id VARCHAR NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL,
+    x_max INTEGER NOT NULL,
+    y_max INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS rovers (
+    id VARCHAR NOT NULL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    facing direction NOT NULL,
+    plateau_id VARCHAR NOT NULL REFERENCES plateaus(id)
+);
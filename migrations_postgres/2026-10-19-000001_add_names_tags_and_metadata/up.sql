@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE plateaus ADD COLUMN name VARCHAR;
+ALTER TABLE plateaus ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE plateaus ADD COLUMN metadata TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS plateaus_name ON plateaus(name);
+
+ALTER TABLE rovers ADD COLUMN name VARCHAR;
+ALTER TABLE rovers ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE rovers ADD COLUMN metadata TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS rovers_plateau_id_name ON rovers(plateau_id, name);
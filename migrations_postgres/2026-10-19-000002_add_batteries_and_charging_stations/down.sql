@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE rovers DROP COLUMN move_cost;
+ALTER TABLE rovers DROP COLUMN turn_cost;
+ALTER TABLE rovers DROP COLUMN battery_capacity;
+ALTER TABLE rovers DROP COLUMN battery_level;
+
+ALTER TABLE plateaus DROP COLUMN charging_stations;
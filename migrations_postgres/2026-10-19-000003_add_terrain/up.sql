@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE plateaus ADD COLUMN terrain TEXT NOT NULL DEFAULT '{}';
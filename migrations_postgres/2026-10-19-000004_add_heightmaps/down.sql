@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE rovers DROP COLUMN max_slope;
+
+ALTER TABLE plateaus DROP COLUMN heightmap;
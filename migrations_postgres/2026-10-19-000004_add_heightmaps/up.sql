@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE plateaus ADD COLUMN heightmap TEXT;
+
+ALTER TABLE rovers ADD COLUMN max_slope INTEGER;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS history (
+    id SERIAL PRIMARY KEY,
+    plateau_id VARCHAR NOT NULL REFERENCES plateaus(id),
+    rover_id VARCHAR NOT NULL,
+    batch VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    command TEXT NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    facing direction NOT NULL,
+    poses TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX history_plateau_id ON history (plateau_id);
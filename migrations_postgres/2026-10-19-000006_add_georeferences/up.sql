@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE plateaus ADD COLUMN georeference TEXT;
@@ -0,0 +1,103 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_int;
+use std::path::Path;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use diesel::{Connection, SqliteConnection};
+use diesel_migrations::MigrationConnection;
+use libsqlite3_sys as ffi;
+
+use crate::Error;
+
+/// How many pages are copied at a time, the database is only locked while they are.
+const PAGES_PER_STEP: c_int = 256;
+
+/// A SQLite database opened through the C API, closed when dropped.
+struct RawDatabase(*mut ffi::sqlite3);
+
+impl RawDatabase {
+  fn open(path: &Path, flags: c_int) -> Result<Self, Error> {
+    let path = CString::new(path.to_string_lossy().as_bytes()).map_err(|error| Error::BackupError(error.to_string()))?;
+    let mut handle = ptr::null_mut();
+    let code = unsafe { ffi::sqlite3_open_v2(path.as_ptr(), &mut handle, flags, ptr::null()) };
+    // A handle is given back even when the database can't be opened, it has to be closed all the same.
+    let database = Self(handle);
+    match code {
+      ffi::SQLITE_OK => {
+        unsafe { ffi::sqlite3_busy_timeout(handle, 30_000) };
+        Ok(database)
+      }
+      _ => Err(database.error()),
+    }
+  }
+
+  fn error(&self) -> Error {
+    let message = match self.0.is_null() {
+      true => "out of memory".to_string(),
+      false => unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(self.0)) }.to_string_lossy().into_owned(),
+    };
+    Error::BackupError(message)
+  }
+
+  /// Copies the whole database into `destination` with the online backup API, a few pages at a time, so that the
+  /// connections of other processes, e.g. `gcs_api`, can go on writing meanwhile.
+  fn copy_to(&self, destination: &RawDatabase) -> Result<(), Error> {
+    let main = c"main".as_ptr();
+    let backup = unsafe { ffi::sqlite3_backup_init(destination.0, main, self.0, main) };
+    if backup.is_null() {
+      return Err(destination.error());
+    }
+    loop {
+      match unsafe { ffi::sqlite3_backup_step(backup, PAGES_PER_STEP) } {
+        ffi::SQLITE_OK => (),
+        ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => thread::sleep(Duration::from_millis(100)),
+        _ => break,
+      }
+    }
+    match unsafe { ffi::sqlite3_backup_finish(backup) } {
+      ffi::SQLITE_OK => Ok(()),
+      _ => Err(destination.error()),
+    }
+  }
+}
+
+impl Drop for RawDatabase {
+  fn drop(&mut self) {
+    unsafe { ffi::sqlite3_close(self.0) };
+  }
+}
+
+/// Copies the SQLite database at `database` into the file `backup`, replacing it. The database can be in use while it
+/// is copied, the backup holds it as it was when the copy ended.
+pub fn backup(database: &Path, backup: &Path) -> Result<(), Error> {
+  let source = RawDatabase::open(database, ffi::SQLITE_OPEN_READONLY)?;
+  let destination = RawDatabase::open(backup, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE)?;
+  source.copy_to(&destination)
+}
+
+/// Replaces the SQLite database at `database` with the file `backup`, made with [`backup`].
+///
+/// The backup has to be a database of `gcs`, of a schema no later than the one of this build. The schema of an earlier
+/// backup is left as it was, to be brought up to date by [`crate::db::migrate`].
+pub fn restore(backup: &Path, database: &Path) -> Result<(), Error> {
+  if !backup.is_file() {
+    return Err(Error::BackupError(format!("No such file: {}", backup.display())));
+  }
+  let versions = SqliteConnection::establish(&backup.to_string_lossy())
+    .ok()
+    .and_then(|conn| conn.previously_run_migration_versions().ok())
+    .ok_or_else(|| Error::BackupError(format!("{} isn't a database of gcs", backup.display())))?;
+  let latest = crate::db::schema_version();
+  if let Some(version) = versions.iter().filter(|version| **version > latest).max() {
+    return Err(Error::SchemaError(format!(
+      "The backup schema is at version {}, later than version {} of this build. Upgrade gcs to restore this backup",
+      version, latest
+    )));
+  }
+
+  let source = RawDatabase::open(backup, ffi::SQLITE_OPEN_READONLY)?;
+  let destination = RawDatabase::open(database, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE)?;
+  source.copy_to(&destination)
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Motion};
+
+/// The energy of a rover, drained by every motion it carries out.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Battery {
+  level: i32,
+  capacity: i32,
+  turn_cost: i32,
+  move_cost: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BatteryRequest {
+  pub capacity: i32,
+  /// Defaults to a full battery.
+  #[serde(default)]
+  pub level: Option<i32>,
+  #[serde(default = "default_cost")]
+  pub turn_cost: i32,
+  #[serde(default = "default_cost")]
+  pub move_cost: i32,
+}
+
+fn default_cost() -> i32 {
+  1
+}
+
+impl Battery {
+  /// A full battery. The capacity and the costs can't be negative, a negative cost would charge the battery.
+  pub fn new(capacity: i32, turn_cost: i32, move_cost: i32) -> Result<Self, Error> {
+    if capacity < 0 {
+      return Err(Error::BatteryError(format!("Expected a non-negative capacity but found {}", capacity)));
+    }
+    if turn_cost < 0 || move_cost < 0 {
+      return Err(Error::BatteryError(format!(
+        "Expected non-negative turn and move costs but found {} and {}",
+        turn_cost, move_cost
+      )));
+    }
+    Ok(Self {
+      level: capacity,
+      capacity,
+      turn_cost,
+      move_cost,
+    })
+  }
+
+  pub fn level(&self) -> i32 {
+    self.level
+  }
+
+  pub fn capacity(&self) -> i32 {
+    self.capacity
+  }
+
+  pub fn turn_cost(&self) -> i32 {
+    self.turn_cost
+  }
+
+  pub fn move_cost(&self) -> i32 {
+    self.move_cost
+  }
+
+  pub fn cost(&self, motion: &Motion) -> i32 {
+    match motion {
+      Motion::TurnLeft | Motion::TurnRight => self.turn_cost,
+      Motion::MoveStraight => self.move_cost,
+    }
+  }
+
+  pub fn can_afford(&self, cost: i32) -> bool {
+    self.level >= cost
+  }
+
+  pub fn drain(&mut self, cost: i32) {
+    self.level -= cost;
+  }
+
+  pub fn charge(&mut self) {
+    self.level = self.capacity;
+  }
+
+  /// Whether the battery can't pay for any motion anymore.
+  pub fn is_depleted(&self) -> bool {
+    !self.can_afford(self.turn_cost.min(self.move_cost))
+  }
+
+  pub(crate) fn from_db(level: Option<i32>, capacity: Option<i32>, turn_cost: Option<i32>, move_cost: Option<i32>) -> Option<Self> {
+    capacity.map(|capacity| Self {
+      level: level.unwrap_or(capacity),
+      capacity,
+      turn_cost: turn_cost.unwrap_or_else(default_cost),
+      move_cost: move_cost.unwrap_or_else(default_cost),
+    })
+  }
+}
+
+impl Battery {
+  /// The request creating a rover with this battery, as the REST API takes it.
+  pub fn to_request(&self) -> BatteryRequest {
+    BatteryRequest {
+      capacity: self.capacity,
+      level: Some(self.level),
+      turn_cost: self.turn_cost,
+      move_cost: self.move_cost,
+    }
+  }
+}
+
+impl BatteryRequest {
+  /// The level, when given, must be between 0 and the capacity.
+  pub fn to_battery(&self) -> Result<Battery, Error> {
+    let battery = Battery::new(self.capacity, self.turn_cost, self.move_cost)?;
+    match self.level {
+      Some(level) if !(0..=self.capacity).contains(&level) => Err(Error::BatteryError(format!(
+        "Expected a level between 0 and {} but found {}",
+        self.capacity, level
+      ))),
+      level => Ok(Battery {
+        level: level.unwrap_or(self.capacity),
+        ..battery
+      }),
+    }
+  }
+}
+
+impl fmt::Display for Battery {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}/{}", self.level, self.capacity)
+  }
+}
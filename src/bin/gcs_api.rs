@@ -1,8 +1,6 @@
-use std::time::Duration;
 use std::{env, io};
 
-use actix_web::{middleware, App, HttpServer};
-use diesel::{r2d2::ConnectionManager, SqliteConnection};
+use actix_web::{middleware, web::Data, App, HttpServer};
 use dotenv::dotenv;
 
 use gcs::*;
@@ -13,30 +11,42 @@ async fn main() -> Result<(), io::Error> {
   env_logger::init();
 
   let database_url = env::var("DATABASE_URL").expect("DATABASE_URL");
-  let manager = ConnectionManager::<SqliteConnection>::new(database_url);
-  let pool = r2d2::Pool::builder()
-    .connection_customizer(Box::new(ConnectionOptions {
-      enable_wal: true,
-      enable_foreign_keys: true,
-      busy_timeout: Some(Duration::from_secs(30)),
-    }))
-    .build(manager)
-    .expect("Failed to create pool");
+  let store = match store::open(&database_url) {
+    Ok(store) => store,
+    Err(error) => {
+      eprintln!("Failed to open the database: {}", error);
+      std::process::exit(1);
+    }
+  };
 
   HttpServer::new(move || {
     App::new()
-      // Set up DB pool to be used with web::Data<Pool> extractor
-      .data(pool.clone())
+      // Set up the store to be used with web::Data<dyn Store> extractor
+      .app_data(Data::from(store.clone()))
       // enable logger - always register actix-web Logger middleware last
       .wrap(middleware::Logger::default())
       // register HTTP requests handlers
+      .service(plateau::async_create_map)
       .service(plateau::async_create)
       .service(plateau::async_list)
       .service(plateau::async_get)
+      .service(plateau::async_delete)
       .service(plateau::async_create_rover)
       .service(plateau::async_list_rovers)
       .service(plateau::async_get_rover)
+      .service(plateau::async_delete_rover)
       .service(plateau::async_move_rover)
+      .service(plateau::async_move_group)
+      .service(plateau::async_move_rovers)
+      .service(plateau::async_set_terrain)
+      .service(plateau::async_set_heightmap)
+      .service(plateau::async_render_svg)
+      .service(plateau::async_get_history)
+      .service(plateau::async_replay_svg)
+      .service(plateau::async_replay_text)
+      .service(plateau::async_set_georeference)
+      .service(plateau::async_export_geojson)
+      .service(plateau::async_export_kml)
   })
   .bind("0.0.0.0:9090")?
   .run()
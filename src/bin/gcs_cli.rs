@@ -1,31 +1,265 @@
+use std::collections::VecDeque;
 use std::env;
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{mpsc, Arc};
 use std::time::Duration;
 
-use clap::Parser;
-use diesel::{r2d2::ConnectionManager, SqliteConnection};
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
+use notify::{RecursiveMode, Watcher};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
+use serde::Serialize;
 use uuid::Uuid;
 
+use gcs::store::Store;
 use gcs::*;
 
 #[derive(Debug, Parser)]
 #[clap(version, author, about)]
 struct Args {
-  #[clap(short, long, help = "Specifies the input text file.")]
+  #[clap(flatten)]
+  mission: MissionArgs,
+  #[clap(long, global = true, value_name = "FORMAT", default_value = "text", possible_values = &["text", "json", "ndjson", "csv"])]
+  #[clap(help = "Prints the final rovers and the listings as text, a JSON array, one JSON object per line or CSV.")]
+  format: output::OutputFormat,
+  #[clap(long, global = true, value_name = "URL")]
+  #[clap(help = "Goes through the REST API of the gcs_api server at URL, e.g. http://localhost:9090, instead of opening the database.")]
+  server: Option<String>,
+  #[clap(long, global = true, conflicts_with = "server")]
+  #[clap(help = "Keeps the plateaus and rovers in memory for this run only, instead of opening the database.")]
+  ephemeral: bool,
+  #[clap(long, help = "List all the plateaus in the database.")]
+  list_plateaus: bool,
+  #[clap(long, value_name = "PLATEAU", help = "Lists the rovers from the specified plateau id or name.")]
+  list_rovers: Option<String>,
+  #[clap(long, requires = "plateau", number_of_values = 2, value_names = &["TAG", "PATH"])]
+  #[clap(help = "Moves every rover tagged with TAG along PATH on the specified plateau.")]
+  move_group: Option<Vec<String>>,
+  #[clap(subcommand)]
+  command: Option<Command>,
+}
+
+/// What a mission reads from the input and how it moves the rovers, given to `mission run` or to `gcs_cli` itself.
+#[derive(Debug, clap::Args)]
+struct MissionArgs {
+  #[clap(short, long, help = "Specifies the input text file, `-` for the standard input.")]
   #[clap(parse(from_os_str))]
   input: Option<std::path::PathBuf>,
+  #[clap(long, value_name = "FORMAT", possible_values = &["text", "json", "yaml", "toml"])]
+  #[clap(help = "The format of the input, told from the extension of the input file by default: a text file or a JSON, YAML or TOML mission.")]
+  input_format: Option<mission::MissionFormat>,
   #[clap(short, long, help = "Specifies the output text file.")]
   #[clap(parse(from_os_str))]
   output: Option<std::path::PathBuf>,
-  #[clap(long, help = "List all the plateaus in the database.")]
-  list_plateaus: bool,
-  #[clap(long, value_name = "PLATEAU_ID", help = "Lists the rovers from the specified plateau id.")]
-  list_rovers: Option<Uuid>,
-  #[clap(short, long, value_name = "PLATEAU_ID", help = "Loads the specified plateau from id.")]
-  plateau: Option<Uuid>,
+  #[clap(short, long, value_name = "PLATEAU", help = "Loads the specified plateau from id or name.")]
+  plateau: Option<String>,
+  #[clap(long, help = "Moves all the rovers at the same time, one motion per tick, and prints every tick.")]
+  concurrent: bool,
+  #[clap(long, requires = "concurrent", value_name = "POLICY", default_value = "skip", possible_values = &["skip", "wait", "yield"])]
+  #[clap(help = "What a blocked rover does with its motion: skip it, wait until the way is free or wait and yield in a deadlock.")]
+  on_blocked: simulation::BlockedPolicy,
+  #[clap(long, value_name = "CAPACITY", help = "Gives every rover read from the input a full battery of CAPACITY.")]
+  battery: Option<i32>,
+  #[clap(long, requires = "battery", value_name = "COST", default_value = "1", help = "The energy a turn takes.")]
+  turn_cost: i32,
+  #[clap(long, requires = "battery", value_name = "COST", default_value = "1", help = "The energy a move straight takes.")]
+  move_cost: i32,
+  #[clap(long, value_name = "X,Y", multiple_occurrences = true, parse(try_from_str = parse_cell))]
+  #[clap(help = "Adds a charging station to the plateau read from the input.")]
+  charging_station: Vec<(i32, i32)>,
+  #[clap(long, value_name = "FILE", help = "Loads the heightmap of the plateau, a PGM image or a CSV grid.")]
+  #[clap(parse(from_os_str))]
+  heightmap: Option<std::path::PathBuf>,
+  #[clap(
+    long,
+    value_name = "HEIGHT",
+    help = "The rovers read from the input can only step across height differences below this."
+  )]
+  max_slope: Option<i32>,
+  #[clap(long, conflicts_with = "plateau", value_name = "FILE")]
+  #[clap(help = "Creates the plateau, its obstacles and its rovers from a map file, the input then holds the motion paths.")]
+  #[clap(parse(from_os_str))]
+  map: Option<std::path::PathBuf>,
+  #[clap(long, help = "Draws the plateau with all its rovers once they have moved.")]
+  render: bool,
+  #[clap(long, requires = "render", help = "Draws the trajectories of the rovers moved on the plateau.")]
+  trajectories: bool,
+  #[clap(long, value_name = "LAT,LON,CELL_SIZE[,ROTATION]", allow_hyphen_values = true)]
+  #[clap(help = "Places the south-west corner of the plateau at LAT,LON, with cells of CELL_SIZE metres, turned ROTATION degrees clockwise.")]
+  georeference: Option<Georeference>,
+  #[clap(long, value_name = "FORMAT", possible_values = &["geojson", "kml"])]
+  #[clap(help = "Exports the georeferenced plateau with its obstacles, rovers and trajectories once they have moved.")]
+  export: Option<geo::ExportFormat>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Creates, lists, shows and deletes plateaus.
+  #[clap(subcommand)]
+  Plateau(PlateauCommand),
+  /// Creates, lists, shows, moves and deletes the rovers of a plateau.
+  #[clap(subcommand)]
+  Rover(RoverCommand),
+  /// Runs missions read from the input.
+  #[clap(subcommand)]
+  Mission(MissionCommand),
+  /// Looks after the database.
+  #[clap(subcommand)]
+  Db(DbCommand),
+  /// Starts an interactive shell to select plateaus and rovers and move them, with line editing, history and completion.
+  Repl,
+  /// Shows a full-screen dashboard of the selected plateau and its rovers, refreshed as the database changes, with a
+  /// command line taking the commands of the shell.
+  Tui,
+  /// Replays the command history of a plateau as an animated SVG image, or as text frames.
+  Replay {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+    #[clap(long, help = "Prints one text frame per step instead of an animated SVG image.")]
+    frames: bool,
+    #[clap(long, value_name = "SECONDS", default_value = "0.5", help = "How long every frame of the animation is shown.")]
+    frame_duration: f64,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum PlateauCommand {
+  /// Creates a plateau.
+  Create {
+    #[clap(value_name = "X_MAX", help = "The largest x co-ordinate of the plateau.")]
+    x_max: i32,
+    #[clap(value_name = "Y_MAX", help = "The largest y co-ordinate of the plateau.")]
+    y_max: i32,
+    #[clap(long, help = "Names the plateau, the name can then be used instead of its id.")]
+    name: Option<String>,
+    #[clap(long = "tag", value_name = "TAG", multiple_occurrences = true, help = "Tags the plateau.")]
+    tags: Vec<String>,
+    #[clap(long, value_name = "X,Y", multiple_occurrences = true, parse(try_from_str = parse_cell))]
+    #[clap(help = "Adds a charging station to the plateau.")]
+    charging_station: Vec<(i32, i32)>,
+    #[clap(long, value_name = "FILE", help = "Loads the heightmap of the plateau, a PGM image or a CSV grid.")]
+    #[clap(parse(from_os_str))]
+    heightmap: Option<std::path::PathBuf>,
+    #[clap(long, value_name = "LAT,LON,CELL_SIZE[,ROTATION]", allow_hyphen_values = true)]
+    #[clap(help = "Places the south-west corner of the plateau at LAT,LON, with cells of CELL_SIZE metres, turned ROTATION degrees clockwise.")]
+    georeference: Option<Georeference>,
+  },
+  /// Lists the plateaus, the latest first.
+  List {
+    #[clap(long, value_name = "COUNT", default_value = "50", help = "The largest number of plateaus listed.")]
+    limit: i64,
+  },
+  /// Shows a plateau with its rovers.
+  Show {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+  },
+  /// Deletes a plateau with its rovers and their history.
+  Delete {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum RoverCommand {
+  /// Lands a rover on a free cell of a plateau.
+  Create {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+    #[clap(value_name = "X", help = "The x co-ordinate of the rover.")]
+    x: i32,
+    #[clap(value_name = "Y", help = "The y co-ordinate of the rover.")]
+    y: i32,
+    #[clap(value_name = "HEADING", help = "The heading of the rover: N, E, S or W.")]
+    heading: String,
+    #[clap(long, help = "Names the rover, the name can then be used instead of its id.")]
+    name: Option<String>,
+    #[clap(long = "tag", value_name = "TAG", multiple_occurrences = true, help = "Tags the rover.")]
+    tags: Vec<String>,
+    #[clap(long, value_name = "CAPACITY", help = "Gives the rover a full battery of CAPACITY.")]
+    battery: Option<i32>,
+    #[clap(long, requires = "battery", value_name = "COST", default_value = "1", help = "The energy a turn takes.")]
+    turn_cost: i32,
+    #[clap(long, requires = "battery", value_name = "COST", default_value = "1", help = "The energy a move straight takes.")]
+    move_cost: i32,
+    #[clap(long, value_name = "HEIGHT", help = "The rover can only step across height differences below this.")]
+    max_slope: Option<i32>,
+  },
+  /// Lists the rovers of a plateau, the latest first.
+  List {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+  },
+  /// Shows a rover.
+  Show {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+    #[clap(value_name = "ROVER", help = "The rover id or name.")]
+    rover: String,
+  },
+  /// Moves a rover along a motion path.
+  Move {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+    #[clap(value_name = "ROVER", help = "The rover id or name.")]
+    rover: String,
+    #[clap(value_name = "PATH", help = "The motion path, e.g. LMLMM.")]
+    path: String,
+  },
+  /// Deletes a rover with its history.
+  Delete {
+    #[clap(value_name = "PLATEAU", help = "The plateau id or name.")]
+    plateau: String,
+    #[clap(value_name = "ROVER", help = "The rover id or name.")]
+    rover: String,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum MissionCommand {
+  /// Creates or loads the plateaus of the input and moves their rovers, like `gcs_cli` without a command.
+  Run(MissionArgs),
+}
+
+#[derive(Debug, Subcommand)]
+enum DbCommand {
+  /// Shows how many plateaus, rovers and history entries the database holds, and the version of its schema.
+  Info,
+  /// Runs the migrations the database lacks. The other commands run them as well before opening the database.
+  Migrate,
+  /// Copies the SQLite database into a file with the online backup API, safe while gcs_api is writing to it.
+  Backup {
+    #[clap(value_name = "FILE", help = "The backup file, replaced if it exists.")]
+    file: PathBuf,
+  },
+  /// Replaces the SQLite database with a backup made with `db backup`, then brings its schema up to date.
+  Restore {
+    #[clap(value_name = "FILE", help = "The backup file.")]
+    file: PathBuf,
+  },
+  /// Writes a JSON snapshot of every plateau with its rovers and history, which doesn't depend on the schema.
+  Export {
+    #[clap(value_name = "FILE", help = "The snapshot file, the standard output if left out.")]
+    file: Option<PathBuf>,
+  },
+  /// Imports a JSON snapshot made with `db export`, replacing the plateaus with the same ids.
+  Import {
+    #[clap(value_name = "FILE", help = "The snapshot file, - for the standard input.")]
+    file: PathBuf,
+  },
+}
+
+fn parse_cell(s: &str) -> Result<(i32, i32), Error> {
+  match s.split_once(',') {
+    Some((x, y)) => Ok((x.trim().parse()?, y.trim().parse()?)),
+    None => Err(Error::CoOrdinateParseError(format!("Expected X,Y but found '{}'", s))),
+  }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -34,49 +268,583 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
   // Read environment variables from .env file.
   dotenv().ok();
 
-  // Initialize the database connection pool.
-  let database_url = env::var("DATABASE_URL").expect("DATABASE_URL");
-  let manager = ConnectionManager::<SqliteConnection>::new(database_url);
-  let pool = r2d2::Pool::builder()
-    .connection_customizer(Box::new(ConnectionOptions {
-      enable_wal: true,
-      enable_foreign_keys: true,
-      busy_timeout: Some(Duration::from_secs(30)),
-    }))
-    .build(manager)
-    .expect("Failed to create pool");
+  // Open the store: the database, unless a server is to be gone through or nothing is to be kept.
+  let database_url = match (&args.server, args.ephemeral) {
+    (None, false) => match env::var("DATABASE_URL") {
+      Ok(database_url) => Some(database_url),
+      Err(error) => {
+        eprint!("DATABASE_URL must be set, or --server or --ephemeral given: ");
+        return Err(Box::new(error));
+      }
+    },
+    _ => None,
+  };
+  let database = match database_url
+    .as_deref()
+    .filter(|database_url| *database_url != store::MEMORY_URL)
+    .map(store::DatabaseStore::open)
+    .transpose()
+  {
+    Ok(database) => database,
+    Err(error) => {
+      eprint!("Failed to open the database: ");
+      return Err(Box::new(error));
+    }
+  };
+  // The `db` commands take the schema as it is, the other commands bring it up to date first.
+  if let (Some(database), false) = (&database, matches!(args.command, Some(Command::Db(_)))) {
+    if let Err(error) = database.migrate() {
+      eprint!("Failed to open the database: ");
+      return Err(Box::new(error));
+    }
+  }
+  let station: Arc<dyn Store> = match (&args.server, &database) {
+    (Some(url), _) => Arc::new(client::Client::new(url)),
+    (None, Some(database)) => Arc::new(database.clone()),
+    (None, None) => Arc::new(memory::MemoryStore::new()),
+  };
+
+  match (&args.command, &database) {
+    (Some(Command::Plateau(command)), _) => return plateau_command(command, args.format, &*station),
+    (Some(Command::Rover(command)), _) => return rover_command(command, args.format, &*station),
+    (Some(Command::Mission(MissionCommand::Run(mission))), _) => return run_mission(mission, args.format, &*station),
+    (Some(Command::Repl), _) => return run_repl(station),
+    (Some(Command::Tui), _) => {
+      let database_file = database
+        .as_ref()
+        .and(database_url.as_deref())
+        .filter(|database_url| !store::is_postgres_url(database_url));
+      return run_tui(&*station, database_file);
+    }
+    (Some(Command::Db(command)), Some(database)) => return db_command(command, args.format, database, database_url.as_deref().unwrap()),
+    (Some(Command::Db(_)), None) => {
+      eprint!(
+        "This command opens the database, it can't be used with --server, --ephemeral or a DATABASE_URL of {}: ",
+        store::MEMORY_URL
+      );
+      return Err(Box::new(io::Error::from(io::ErrorKind::Unsupported)));
+    }
+    _ => (),
+  }
+
+  if let Some(Command::Replay {
+    plateau,
+    frames,
+    frame_duration,
+  }) = &args.command
+  {
+    let replay = station
+      .find_plateau(plateau)
+      .and_then(|plateau| station.replay_plateau(Uuid::from_str(plateau.id()).unwrap()))
+      .and_then(|(plateau, replay)| match *frames {
+        true => Ok(render::ascii_frames(&plateau, &replay)),
+        false => render::animated_svg(&plateau, &replay, *frame_duration),
+      });
+    let replay = match replay {
+      Ok(replay) => replay,
+      Err(error) => {
+        eprint!("Failed to replay plateau '{}': ", plateau);
+        return Err(Box::new(error));
+      }
+    };
+    match &args.mission.output {
+      Some(output_path) => {
+        if let Err(error) = std::fs::write(output_path, replay) {
+          eprint!("Failed to write to output file: ");
+          return Err(Box::new(error));
+        }
+      }
+      None => print!("{}", replay),
+    }
+    return Ok(());
+  }
 
   if args.list_plateaus {
-    let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-    match plateau::list_plateaus(50, &conn) {
+    match station.list_plateaus(50) {
       Err(error) => {
         eprint!("Failed to read plateaus from database: ");
         return Err(Box::new(error));
       }
       Ok(plateaus) => {
-        println!("{}", plateaus);
+        print_records(args.format, &plateaus, &plateaus)?;
         return Ok(());
       }
     }
   }
 
-  if let Some(plateau_id) = args.list_rovers {
-    let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-    match plateau::list_rovers(plateau_id, 50, &conn) {
+  if let Some(plateau_key) = &args.list_rovers {
+    let rovers = station
+      .find_plateau(plateau_key)
+      .and_then(|plateau| station.list_rovers(Uuid::from_str(plateau.id()).unwrap(), 50));
+    match rovers {
       Err(error) => {
         eprint!("Failed to read rovers from database: ");
         return Err(Box::new(error));
       }
       Ok(rovers) => {
-        println!("{}", rovers);
+        print_records(args.format, &rovers, &rovers)?;
         return Ok(());
       }
     }
   }
 
-  // Initialize the input.
+  if let (Some(plateau_key), Some(move_group)) = (&args.mission.plateau, &args.move_group) {
+    let (tag, path) = (&move_group[0], &move_group[1]);
+    let motion_vector = Motion::parse_path(path)?;
+    let reports = station
+      .find_plateau(plateau_key)
+      .and_then(|plateau| station.move_group(Uuid::from_str(plateau.id()).unwrap(), tag, motion_vector));
+    match reports {
+      Err(error) => {
+        eprint!("Failed to move the rovers tagged '{}': ", tag);
+        return Err(Box::new(error));
+      }
+      Ok(reports) => {
+        for report in reports {
+          println!("{}", report);
+        }
+        return Ok(());
+      }
+    }
+  }
+
+  run_mission(&args.mission, args.format, &*station)
+}
+
+/// Prints the text of records, or the records themselves in a machine-readable format.
+fn print_records<T: Serialize + output::CsvRecord>(format: output::OutputFormat, text: &dyn fmt::Display, records: &[T]) -> Result<(), serde_json::Error> {
+  match format {
+    output::OutputFormat::Text => println!("{}", text),
+    format => print!("{}", output::records(format, records)?),
+  }
+  Ok(())
+}
+
+fn plateau_command(command: &PlateauCommand, format: output::OutputFormat, station: &dyn Store) -> Result<(), Box<dyn std::error::Error>> {
+  match command {
+    PlateauCommand::Create {
+      x_max,
+      y_max,
+      name,
+      tags,
+      charging_station,
+      heightmap,
+      georeference,
+    } => {
+      let heightmap = match heightmap {
+        Some(heightmap_path) => match std::fs::read(heightmap_path).map(|content| Heightmap::parse(&content)) {
+          Ok(Ok(heightmap)) => Some(heightmap),
+          Ok(Err(error)) => {
+            eprint!("Invalid heightmap {:?}: ", heightmap_path);
+            return Err(Box::new(error));
+          }
+          Err(error) => {
+            eprint!("Failed to read file {:?}: ", heightmap_path);
+            return Err(Box::new(error));
+          }
+        },
+        None => None,
+      };
+      let plateau_request = plateau::PlateauRequest {
+        x_max: *x_max,
+        y_max: *y_max,
+        name: name.clone(),
+        tags: tags.iter().cloned().collect(),
+        metadata: serde_json::Value::Null,
+        charging_stations: charging_station.iter().copied().collect(),
+        terrain: TerrainMap::new(),
+        heightmap,
+        georeference: georeference.clone(),
+      };
+      let plateau = match plateau_request.to_plateau() {
+        Ok(plateau) => plateau,
+        Err(error) => {
+          eprint!("Invalid plateau: ");
+          return Err(Box::new(error));
+        }
+      };
+      match station.create_plateau(plateau) {
+        Ok(plateau) => match format {
+          output::OutputFormat::Text => println!("Created plateau with id '{}' and bounds '{}'.", plateau.id(), plateau),
+          format => print!("{}", output::records(format, &[plateau])?),
+        },
+        Err(error) => {
+          eprint!("Failed to create plateau in database: ");
+          return Err(Box::new(error));
+        }
+      }
+    }
+    PlateauCommand::List { limit } => match station.list_plateaus(*limit) {
+      Ok(plateaus) => print_records(format, &plateaus, &plateaus)?,
+      Err(error) => {
+        eprint!("Failed to read plateaus from database: ");
+        return Err(Box::new(error));
+      }
+    },
+    PlateauCommand::Show { plateau: plateau_key } => {
+      let shown = station.find_plateau(plateau_key).and_then(|plateau| {
+        station
+          .list_rovers(Uuid::from_str(plateau.id()).unwrap(), i64::MAX)
+          .map(|rovers| (plateau, rovers))
+      });
+      match shown {
+        Ok((plateau, rovers)) => match format {
+          output::OutputFormat::Text => {
+            match plateau.name() {
+              Some(name) => println!("Plateau {} ({}): {}", plateau.id(), name, plateau),
+              None => println!("Plateau {}: {}", plateau.id(), plateau),
+            }
+            println!("{}", rovers);
+          }
+          format => print!("{}", output::records(format, &[plateau])?),
+        },
+        Err(error) => {
+          eprint!("Failed to load plateau '{}': ", plateau_key);
+          return Err(Box::new(error));
+        }
+      }
+    }
+    PlateauCommand::Delete { plateau: plateau_key } => {
+      let deleted = station
+        .find_plateau(plateau_key)
+        .and_then(|plateau| station.delete_plateau(Uuid::from_str(plateau.id()).unwrap()));
+      match deleted {
+        Ok(1) => println!("Deleted plateau '{}' and its rover.", plateau_key),
+        Ok(rovers) => println!("Deleted plateau '{}' and its {} rovers.", plateau_key, rovers),
+        Err(error) => {
+          eprint!("Failed to delete plateau '{}': ", plateau_key);
+          return Err(Box::new(error));
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+fn rover_command(command: &RoverCommand, format: output::OutputFormat, station: &dyn Store) -> Result<(), Box<dyn std::error::Error>> {
+  let plateau_key = match command {
+    RoverCommand::Create { plateau, .. }
+    | RoverCommand::List { plateau }
+    | RoverCommand::Show { plateau, .. }
+    | RoverCommand::Move { plateau, .. }
+    | RoverCommand::Delete { plateau, .. } => plateau,
+  };
+  let plateau_id = match station.find_plateau(plateau_key) {
+    Ok(plateau) => Uuid::from_str(plateau.id()).unwrap(),
+    Err(error) => {
+      eprint!("Failed to load plateau '{}': ", plateau_key);
+      return Err(Box::new(error));
+    }
+  };
+  let find_rover = |rover_key: &str| match station.find_rover(plateau_id, rover_key) {
+    Ok(rover) => Ok(rover),
+    Err(error) => {
+      eprint!("Failed to load rover '{}': ", rover_key);
+      Err(error)
+    }
+  };
+  match command {
+    RoverCommand::Create {
+      x,
+      y,
+      heading,
+      name,
+      tags,
+      battery,
+      turn_cost,
+      move_cost,
+      max_slope,
+      ..
+    } => {
+      let rover_request = Pose::from_str(&format!("{} {} {}", x, y, heading)).map(|pose| rover::RoverRequest {
+        x: pose.x(),
+        y: pose.y(),
+        facing: pose.facing(),
+        name: name.clone(),
+        tags: tags.iter().cloned().collect(),
+        metadata: serde_json::Value::Null,
+        battery: battery.map(|capacity| BatteryRequest {
+          capacity,
+          level: None,
+          turn_cost: *turn_cost,
+          move_cost: *move_cost,
+        }),
+        max_slope: *max_slope,
+      });
+      let rover = match rover_request.and_then(|rover_request| rover_request.to_rover()) {
+        Ok(rover) => rover,
+        Err(error) => {
+          eprint!("Invalid rover: ");
+          return Err(Box::new(error));
+        }
+      };
+      match station.place_rover(plateau_id, rover) {
+        Ok(rover) => match format {
+          output::OutputFormat::Text => println!("Created rover with id '{}' and pose '{}'", rover.id(), rover.pose()),
+          format => print!("{}", output::records(format, &[rover])?),
+        },
+        Err(error) => {
+          eprint!("Failed to create rover: ");
+          return Err(Box::new(error));
+        }
+      }
+    }
+    RoverCommand::List { .. } => match station.list_rovers(plateau_id, i64::MAX) {
+      Ok(rovers) => print_records(format, &rovers, &rovers)?,
+      Err(error) => {
+        eprint!("Failed to read rovers from database: ");
+        return Err(Box::new(error));
+      }
+    },
+    RoverCommand::Show { rover, .. } => {
+      let rover = find_rover(rover)?;
+      print_records(format, &rover, std::slice::from_ref(&rover))?;
+    }
+    RoverCommand::Move { rover, path, .. } => {
+      let rover = find_rover(rover)?;
+      let motion_vector = match Motion::parse_path(path) {
+        Ok(motion_vector) => motion_vector,
+        Err(error) => {
+          eprint!("Invalid motion path '{}': ", path);
+          return Err(Box::new(error));
+        }
+      };
+      match station.move_rover(plateau_id, Uuid::from_str(rover.id()).unwrap(), motion_vector) {
+        Ok(report) => print_records(format, &report, std::slice::from_ref(&report.rover))?,
+        Err(error) => {
+          eprint!("Failed to move rover in database: ");
+          return Err(Box::new(error));
+        }
+      }
+    }
+    RoverCommand::Delete { rover, .. } => {
+      let rover = find_rover(rover)?;
+      match station.delete_rover(plateau_id, Uuid::from_str(rover.id()).unwrap()) {
+        Ok(()) => println!("Deleted rover '{}'.", rover.label()),
+        Err(error) => {
+          eprint!("Failed to delete rover '{}': ", rover.label());
+          return Err(Box::new(error));
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Reads commands until `quit` or the end of the input. On a terminal the history is kept in `~/.gcs_history`.
+fn run_repl(station: Arc<dyn Store>) -> Result<(), Box<dyn std::error::Error>> {
+  let mut editor = Editor::<repl::ShellHelper, DefaultHistory>::new()?;
+  editor.set_helper(Some(repl::ShellHelper::new(station.clone())));
+  let history_path = match io::stdin().is_terminal() {
+    true => env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".gcs_history")),
+    false => None,
+  };
+  if let Some(history_path) = &history_path {
+    let _ = editor.load_history(history_path);
+  }
+  println!("Ground control station shell, type help for the commands.");
+
+  let mut session = repl::Session::new();
+  loop {
+    if let Some(helper) = editor.helper_mut() {
+      helper.plateau_id = session.plateau_id();
+    }
+    let line = match editor.readline(&session.prompt()) {
+      Ok(line) => line,
+      // Ctrl-C drops the line being typed, Ctrl-D leaves.
+      Err(ReadlineError::Interrupted) => continue,
+      Err(ReadlineError::Eof) => break,
+      Err(error) => return Err(Box::new(error)),
+    };
+    let _ = editor.add_history_entry(line.as_str());
+    match session.execute(&line, &*station) {
+      Ok(repl::Reply::Print(text)) if text.is_empty() => (),
+      Ok(repl::Reply::Print(text)) => println!("{}", text),
+      Ok(repl::Reply::Quit) => break,
+      Err(error) => eprintln!("{}", error),
+    }
+  }
+
+  if let Some(history_path) = &history_path {
+    let _ = editor.save_history(history_path);
+  }
+  Ok(())
+}
+
+/// Runs the dashboard until it is left, drawing it again whenever the database file changes, whichever process wrote it.
+/// Without a database file, e.g. with PostgreSQL, the dashboard is drawn again every few seconds.
+fn run_tui(station: &dyn Store, database_file: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+  if !io::stdout().is_terminal() {
+    eprint!("The dashboard needs a terminal: ");
+    return Err(Box::new(io::Error::from(io::ErrorKind::Unsupported)));
+  }
+
+  let (sender, receiver) = mpsc::channel();
+  let _watcher = match database_file {
+    Some(database_file) => {
+      // SQLite writes to the database file, to its write-ahead log or to its rollback journal.
+      let database = Path::new(database_file);
+      let file_name = database
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+      let written = [file_name.clone(), format!("{}-wal", file_name), format!("{}-journal", file_name)];
+      let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+          let names = event
+            .paths
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+          if !event.kind.is_access() && names.into_iter().any(|name| written.contains(&name)) {
+            let _ = sender.send(());
+          }
+        }
+      })?;
+      let directory = database
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+      watcher.watch(directory, RecursiveMode::NonRecursive)?;
+      Some(watcher)
+    }
+    None => {
+      std::thread::spawn(move || {
+        while sender.send(()).is_ok() {
+          std::thread::sleep(Duration::from_secs(2));
+        }
+      });
+      None
+    }
+  };
+
+  let mut dashboard = tui::Dashboard::new();
+  dashboard.refresh(station);
+  let mut terminal = ratatui::init();
+  let result = (|| -> io::Result<()> {
+    loop {
+      terminal.draw(|frame| dashboard.draw(frame))?;
+      if event::poll(Duration::from_millis(250))? {
+        if let Event::Key(key) = event::read()? {
+          if key.kind == KeyEventKind::Press && dashboard.handle_key(key, station) {
+            return Ok(());
+          }
+        }
+      }
+      if receiver.try_iter().count() > 0 {
+        dashboard.refresh(station);
+      }
+    }
+  })();
+  ratatui::restore();
+  Ok(result?)
+}
+
+fn db_command(
+  command: &DbCommand,
+  format: output::OutputFormat,
+  database: &store::DatabaseStore,
+  database_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+  if matches!(command, DbCommand::Backup { .. } | DbCommand::Restore { .. }) && store::is_postgres_url(database_url) {
+    eprint!("Backups are made of SQLite databases, use pg_dump or db export for PostgreSQL: ");
+    return Err(Box::new(io::Error::from(io::ErrorKind::Unsupported)));
+  }
+  // A snapshot is taken from, or imported into, the schema of this build.
+  if matches!(command, DbCommand::Export { .. } | DbCommand::Import { .. }) {
+    if let Err(error) = database.migrate() {
+      eprint!("Failed to open the database: ");
+      return Err(Box::new(error));
+    }
+  }
+  match command {
+    DbCommand::Info => match database.connection().and_then(|conn| db::info(&conn)) {
+      Ok(info) => print_records(format, &info, std::slice::from_ref(&info))?,
+      Err(error) => {
+        eprint!("Failed to read the database: ");
+        return Err(Box::new(error));
+      }
+    },
+    DbCommand::Migrate => match database.migrate() {
+      Ok(versions) if versions.is_empty() => println!("The database schema is up to date at version {}", db::schema_version()),
+      Ok(versions) => {
+        for version in versions {
+          println!("Ran migration {}", version);
+        }
+      }
+      Err(error) => {
+        eprint!("Failed to migrate the database: ");
+        return Err(Box::new(error));
+      }
+    },
+    DbCommand::Backup { file } => match backup::backup(Path::new(database_url), file) {
+      Ok(()) => println!("Backed up the database to {}", file.display()),
+      Err(error) => {
+        eprint!("Failed to back up the database: ");
+        return Err(Box::new(error));
+      }
+    },
+    DbCommand::Restore { file } => match backup::restore(file, Path::new(database_url)).and_then(|()| database.migrate()) {
+      Ok(_) => println!("Restored the database from {}", file.display()),
+      Err(error) => {
+        eprint!("Failed to restore the database: ");
+        return Err(Box::new(error));
+      }
+    },
+    DbCommand::Export { file } => {
+      let snapshot = match snapshot::Snapshot::take(database) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+          eprint!("Failed to read the database: ");
+          return Err(Box::new(error));
+        }
+      };
+      let json = serde_json::to_string_pretty(&snapshot)?;
+      match file {
+        Some(file) => {
+          if let Err(error) = std::fs::write(file, json + "\n") {
+            eprint!("Failed to write to output file: ");
+            return Err(Box::new(error));
+          }
+          println!("Exported {} to {}", snapshot, file.display());
+        }
+        None => println!("{}", json),
+      }
+    }
+    DbCommand::Import { file } => {
+      let mut json = String::new();
+      let read = match file.as_os_str() == "-" {
+        true => io::stdin().read_to_string(&mut json),
+        false => File::open(file).and_then(|mut file| file.read_to_string(&mut json)),
+      };
+      if let Err(error) = read {
+        eprint!("Failed to read the snapshot: ");
+        return Err(Box::new(error));
+      }
+      let snapshot = match snapshot::Snapshot::from_str(&json) {
+        Ok(snapshot) => snapshot,
+        Err(error) => {
+          eprint!("Failed to read the snapshot: ");
+          return Err(Box::new(error));
+        }
+      };
+      let summary = snapshot.to_string();
+      if let Err(error) = snapshot.import(database) {
+        eprint!("Failed to import the snapshot: ");
+        return Err(Box::new(error));
+      }
+      println!("Imported {}", summary);
+    }
+  }
+  Ok(())
+}
+
+/// Creates or loads the plateaus of the input and moves their rovers.
+fn run_mission(args: &MissionArgs, format: output::OutputFormat, station: &dyn Store) -> Result<(), Box<dyn std::error::Error>> {
+  // Initialize the input, `-` standing for the standard input read as a file.
   let input: Box<dyn BufRead> = match &args.input {
-    Some(input_path) => match File::open(&input_path) {
+    Some(input_path) if input_path.as_os_str() == "-" => Box::new(BufReader::new(io::stdin())),
+    Some(input_path) => match File::open(input_path) {
       Ok(content) => Box::new(BufReader::new(content)),
       Err(error) => {
         eprint!("Failed to read file {:?}: ", input_path);
@@ -85,29 +853,271 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     },
     None => Box::new(BufReader::new(io::stdin())),
   };
-  let mut numbered_lines_input = input.lines().enumerate().peekable();
 
-  // Read the plateau bounds and create the plateau.
-  let plateau = match args.plateau {
-    Some(plateau_id) => {
-      let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-      match plateau::get_plateau(plateau_id, &conn) {
-        Ok(plateau) => plateau,
+  // A structured mission declares the plateau and its rovers like a map, the input then becomes their motion paths.
+  let input_format = match (args.input_format, &args.input) {
+    (Some(input_format), _) => input_format,
+    (None, Some(input_path)) => mission::MissionFormat::from_path(input_path),
+    (None, None) => mission::MissionFormat::Text,
+  };
+  let interactive = args.input.is_none() && input_format == mission::MissionFormat::Text;
+  let mut input = input;
+  let mut sections = Vec::new();
+  if input_format != mission::MissionFormat::Text {
+    if args.plateau.is_some() || args.map.is_some() {
+      eprint!("Invalid mission: ");
+      return Err(Box::new(Error::MissionParseError(
+        "A mission declares its own plateau, it can't be used with --plateau or --map".into(),
+      )));
+    }
+    let mut content = String::new();
+    if let Err(error) = input.read_to_string(&mut content) {
+      eprint!("Failed to read the mission: ");
+      return Err(Box::new(error));
+    }
+    let maps = mission::Mission::parse_all(&content, input_format).and_then(|missions| {
+      missions
+        .iter()
+        .map(|mission| mission.to_map())
+        .collect::<Result<Vec<(PlateauMap, Vec<String>)>, Error>>()
+    });
+    match maps {
+      Ok(maps) => {
+        for (map, paths) in maps {
+          // Every path is a line, even an empty one for a rover that stays put.
+          sections.push(Section {
+            name: None,
+            map: Some(map),
+            lines: Box::new(paths.into_iter().enumerate().map(|(n, path)| (n, Ok(path)))),
+          });
+        }
+      }
+      Err(error) => {
+        eprint!("Invalid mission: ");
+        return Err(Box::new(error));
+      }
+    }
+  } else if interactive {
+    sections.push(Section {
+      name: None,
+      map: None,
+      lines: Box::new(input.lines().enumerate()),
+    });
+  } else {
+    // A text input read from a file is checked as a whole first, every error in it being reported at once.
+    let mut content = String::new();
+    if let Err(error) = input.read_to_string(&mut content) {
+      eprint!("Failed to read the input: ");
+      return Err(Box::new(error));
+    }
+    let layout = match (&args.plateau, &args.map) {
+      (Some(_), _) => input::Layout::LoadedPlateau,
+      (None, Some(_)) => input::Layout::MapPaths,
+      (None, None) => input::Layout::NewPlateau,
+    };
+    let lines = match input::parse(&content, layout) {
+      Ok(lines) => lines,
+      Err(diagnostics) => {
+        for diagnostic in diagnostics.0.iter() {
+          eprintln!("{}", diagnostic);
+        }
+        return Err(Box::new(Error::InputParseError(diagnostics.summary())));
+      }
+    };
+    // A new plateau may be followed by others, each in a section starting with a `PLATEAU` line.
+    let mut section_lines: Vec<(Option<String>, Vec<NumberedLine>)> = Vec::new();
+    for (n, line) in lines {
+      match line.split_whitespace().collect::<Vec<&str>>()[..] {
+        [word] if layout == input::Layout::NewPlateau && word == input::SECTION => section_lines.push((None, Vec::new())),
+        [word, name] if layout == input::Layout::NewPlateau && word == input::SECTION => section_lines.push((Some(name.to_string()), Vec::new())),
+        _ => {
+          if section_lines.is_empty() {
+            section_lines.push((None, Vec::new()));
+          }
+          section_lines.last_mut().unwrap().1.push((n, Ok(line)));
+        }
+      }
+    }
+    // Without any line, an empty input is reported while reading the plateau bounds.
+    if section_lines.is_empty() {
+      section_lines.push((None, Vec::new()));
+    }
+    for (name, lines) in section_lines {
+      sections.push(Section {
+        name,
+        map: None,
+        lines: Box::new(lines.into_iter()),
+      });
+    }
+  }
+
+  // A map gives the plateau and its rovers, the input then only holds their motion paths.
+  if let Some(map_path) = &args.map {
+    match std::fs::read_to_string(map_path) {
+      Ok(content) => match PlateauMap::from_str(&content) {
+        Ok(map) => {
+          for section in sections.iter_mut() {
+            section.map = Some(map.clone());
+          }
+        }
         Err(error) => {
+          eprint!("Invalid map {:?}: ", map_path);
           return Err(Box::new(error));
         }
+      },
+      Err(error) => {
+        eprint!("Failed to read file {:?}: ", map_path);
+        return Err(Box::new(error));
       }
     }
+  }
+
+  // Several plateaus are processed in turn, the output of each being labelled with its number in the input.
+  let several = sections.len() > 1;
+  let mut results = Vec::new();
+  for (number, section) in sections.into_iter().enumerate() {
+    match run_section(args, format, station, section, several.then_some(number + 1), interactive, input_format)? {
+      Some(result) => results.push(result),
+      None => return Ok(()),
+    }
+  }
+
+  // The machine-readable formats print all the rovers once they have moved, with their plateau if there are several.
+  let records = match (format, several) {
+    (output::OutputFormat::Text, _) => String::new(),
+    (format, false) => {
+      let rovers = results
+        .iter()
+        .flat_map(|(_, rovers)| rovers.iter().map(|(rover, cost)| output::MovedRover { rover, cost: *cost }))
+        .collect::<Vec<output::MovedRover>>();
+      output::records(format, &rovers)?
+    }
+    (format, true) => {
+      let rovers = results
+        .iter()
+        .flat_map(|(plateau, rovers)| {
+          rovers.iter().map(move |(rover, cost)| output::PlateauRover {
+            plateau: plateau.label(),
+            rover: output::MovedRover { rover, cost: *cost },
+          })
+        })
+        .collect::<Vec<output::PlateauRover>>();
+      output::records(format, &rovers)?
+    }
+  };
+  print!("{}", records);
+
+  if let Some(output_path) = &args.output {
+    match File::create(output_path) {
+      Ok(mut file) if format != output::OutputFormat::Text => {
+        if let Err(error) = file.write_all(records.as_bytes()) {
+          eprint!("Failed to write to output file: ",);
+          return Err(Box::new(error));
+        }
+      }
+      Ok(mut file) => {
+        for (number, (plateau, rovers)) in results.iter().enumerate() {
+          let mut text = String::new();
+          if several {
+            text.push_str(&format!("{}\n", header(number + 1, plateau)));
+          }
+          for (rover, _) in rovers {
+            text.push_str(&format!("{}\n", rover.pose()));
+          }
+          if let Err(error) = file.write_all(text.as_bytes()) {
+            eprint!("Failed to write to output file: ",);
+            return Err(Box::new(error));
+          }
+        }
+      }
+      Err(error) => {
+        eprint!("Failed to create output file: ",);
+        return Err(Box::new(error));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// A line of the input with its index.
+type NumberedLine = (usize, io::Result<String>);
+
+/// A plateau with its rovers once they have moved, each with the traversal cost of its moves.
+type Moved = (Plateau, Vec<(Rover, i32)>);
+
+/// A plateau of the input with its rovers.
+struct Section {
+  name: Option<String>,
+  /// The plateau and the rovers of a map or a mission, the lines then only hold the motion paths of the rovers.
+  map: Option<PlateauMap>,
+  lines: Box<dyn Iterator<Item = NumberedLine>>,
+}
+
+/// The line before the output of a plateau when the input holds several.
+fn header(number: usize, plateau: &Plateau) -> String {
+  match plateau.name() {
+    Some(name) => format!("Plateau {} ({}):", number, name),
+    None => format!("Plateau {}:", number),
+  }
+}
+
+/// Creates or loads the plateau of a section and moves its rovers, giving back the plateau and the rovers moved, or
+/// nothing when the plateau bounds are left empty.
+fn run_section(
+  args: &MissionArgs,
+  format: output::OutputFormat,
+  station: &dyn Store,
+  section: Section,
+  number: Option<usize>,
+  interactive: bool,
+  input_format: mission::MissionFormat,
+) -> Result<Option<Moved>, Box<dyn std::error::Error>> {
+  let mut map = section.map;
+  let mut numbered_lines_input = section.lines.peekable();
+  let battery = match args.battery.map(|capacity| Battery::new(capacity, args.turn_cost, args.move_cost)).transpose() {
+    Ok(battery) => battery,
+    Err(error) => {
+      eprint!("Invalid battery: ");
+      return Err(Box::new(error));
+    }
+  };
+
+  // Read the plateau bounds and create the plateau.
+  let plateau = match &args.plateau {
+    None if map.is_some() => {
+      // The charging stations of a mission are kept unless others are given.
+      let mut plateau = map.as_ref().unwrap().plateau.clone();
+      if !args.charging_station.is_empty() {
+        if let Err(error) = plateau.set_charging_stations(args.charging_station.iter().copied().collect()) {
+          eprint!("Invalid charging station: ");
+          return Err(Box::new(error));
+        }
+      }
+      plateau
+    }
+    Some(plateau_key) => match station.find_plateau(plateau_key) {
+      Ok(plateau) => plateau,
+      Err(error) => {
+        eprint!("Failed to load plateau '{}': ", plateau_key);
+        return Err(Box::new(error));
+      }
+    },
     None => {
-      if args.input.is_none() {
+      if interactive {
         println!("Enter the plateau bounds:");
       }
       match numbered_lines_input.next() {
         Some((n, result)) => match result {
           Ok(line) if !line.is_empty() => match Plateau::from_str(&line) {
-            Ok(plateau) => {
-              if args.input.is_none() {
-                println!("Created plateau with id '{}' and bounds '{}'.", plateau.id(), plateau);
+            Ok(mut plateau) => {
+              if let Err(error) = plateau.set_name(section.name) {
+                eprint!("Invalid plateau name: ");
+                return Err(Box::new(error));
+              }
+              if let Err(error) = plateau.set_charging_stations(args.charging_station.iter().copied().collect()) {
+                eprint!("Invalid charging station: ");
+                return Err(Box::new(error));
               }
               plateau
             }
@@ -116,7 +1126,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
               return Err(Box::new(error));
             }
           },
-          Ok(_) => return Ok(()),
+          Ok(_) => return Ok(None),
           Err(error) => {
             return Err(Box::new(error));
           }
@@ -127,22 +1137,105 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       }
     }
   };
-  dprintln!("plateau = {:?}", plateau);
-  {
-    let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-    match plateau::create_plateau(plateau.clone(), &conn) {
+
+  // A new plateau may be followed by a terrain block, a `TERRAIN` line and one row of cells per line from the top.
+  let mut plateau = plateau;
+  if args.plateau.is_none() && map.is_none() && matches!(numbered_lines_input.peek(), Some((_, Ok(line))) if line.trim() == "TERRAIN") {
+    let (n, _) = numbered_lines_input.next().unwrap();
+    let mut rows = Vec::new();
+    for _ in 0..=plateau.y_max() {
+      match numbered_lines_input.next() {
+        Some((_, Ok(line))) if !line.is_empty() => rows.push(line),
+        Some((_, Err(error))) => return Err(Box::new(error)),
+        _ => break,
+      }
+    }
+    if let Err(error) = TerrainMap::from_rows(&rows, plateau.x_max(), plateau.y_max()).and_then(|terrain| plateau.set_terrain(terrain)) {
+      eprint!("Error in terrain block on line {}: ", n + 1);
+      return Err(Box::new(error));
+    }
+  }
+  if let Some(heightmap_path) = &args.heightmap {
+    let heightmap = match std::fs::read(heightmap_path) {
+      Ok(content) => content,
+      Err(error) => {
+        eprint!("Failed to read file {:?}: ", heightmap_path);
+        return Err(Box::new(error));
+      }
+    };
+    if let Err(error) = Heightmap::parse(&heightmap).and_then(|heightmap| plateau.set_heightmap(Some(heightmap))) {
+      eprint!("Invalid heightmap {:?}: ", heightmap_path);
+      return Err(Box::new(error));
+    }
+    // A loaded plateau keeps the new heightmap.
+    if args.plateau.is_some() {
+      if let Err(error) = station.set_plateau_heightmap(Uuid::from_str(plateau.id()).unwrap(), plateau.heightmap().cloned()) {
+        eprint!("Failed to update plateau in database: ");
+        return Err(Box::new(error));
+      }
+    }
+  }
+  if let Some(georeference) = &args.georeference {
+    plateau.set_georeference(Some(georeference.clone()))?;
+    // A loaded plateau keeps the new georeference.
+    if args.plateau.is_some() {
+      if let Err(error) = station.set_plateau_georeference(Uuid::from_str(plateau.id()).unwrap(), Some(georeference.clone())) {
+        eprint!("Failed to update plateau in database: ");
+        return Err(Box::new(error));
+      }
+    }
+  }
+  if args.export.is_some() && plateau.georeference().is_none() {
+    eprint!("Failed to export plateau: ");
+    return Err(Box::new(Error::GeoreferenceError(format!("Plateau {} has no georeference", plateau.label()))));
+  }
+  let mut map_rovers = VecDeque::new();
+  if let Some(map) = &mut map {
+    map.plateau = plateau.clone();
+    // The batteries and slope limits of a mission are kept unless others are given.
+    for rover in map.rovers.iter_mut() {
+      if battery.is_some() {
+        rover.set_battery(battery.clone());
+      }
+      if args.max_slope.is_some() {
+        rover.set_max_slope(args.max_slope);
+      }
+    }
+    match station.create_map(map.clone()) {
+      Ok(created) => {
+        plateau = created.plateau;
+        map_rovers.extend(created.rovers);
+      }
       Err(error) => {
         eprint!("Failed to create plateau in database: ");
         return Err(Box::new(error));
       }
-      _ => (),
     }
+  } else if args.plateau.is_none() {
+    // A server gives the plateau its own id.
+    plateau = match station.create_plateau(plateau) {
+      Ok(plateau) => plateau,
+      Err(error) => {
+        eprint!("Failed to create plateau in database: ");
+        return Err(Box::new(error));
+      }
+    };
+    if interactive {
+      println!("Created plateau with id '{}' and bounds '{}'.", plateau.id(), plateau);
+    }
+  }
+  dprintln!("plateau = {:?}", plateau);
+  if let (Some(number), output::OutputFormat::Text) = (number, format) {
+    println!("{}", header(number, &plateau));
   }
+  let plateau_id = Uuid::from_str(plateau.id()).unwrap();
 
   let mut rover_number: i64 = 0;
   let mut rovers = Vec::new();
+  let mut moves = Vec::new();
+  let mut trajectories: Vec<render::Trajectory> = Vec::new();
   loop {
-    if args.input.is_none() {
+    if interactive && args.plateau.is_none() && map.is_none() {
       println!("Enter the intial pose for rover {}:", rover_number + 1);
     }
 
@@ -150,14 +1243,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       break;
     }
 
-    let mut rover = match args.plateau {
-      Some(plateau_id) => {
-        let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-        match plateau::get_rover_n(rover_number, plateau_id, &conn) {
-          Ok(rover) => rover,
-          Err(_) => return Ok(()),
-        }
-      }
+    // Rovers of a loaded plateau are picked once their motion path has been read.
+    let rover = match args.plateau {
+      Some(_) => None,
+      None if map.is_some() => match map_rovers.pop_front() {
+        Some(rover) => Some(rover),
+        None => break,
+      },
       None => {
         // Get initial pose of the rover and create the rover.
         let initial_pose = match numbered_lines_input.next() {
@@ -169,7 +1261,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 return Err(Box::new(error));
               }
             },
-            Ok(_) => return Ok(()),
+            Ok(_) => break,
             Err(error) => {
               eprintln!("Error: {}", error);
               return Err(Box::new(error));
@@ -177,39 +1269,55 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
           },
           None => return Err(Box::new(Error::InitialPoseNotFound)),
         };
-        let rover = Rover::new(initial_pose.x(), initial_pose.y(), initial_pose.facing());
-        if args.input.is_none() {
-          println!("Created rover with id '{}' and pose '{}'", rover.id(), rover.pose());
-        }
-        {
-          let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-          match plateau::create_rover(Uuid::from_str(plateau.id()).unwrap(), rover.clone(), &conn) {
-            Err(error) => {
-              eprint!("Failed to create rover in database: ");
-              return Err(Box::new(error));
-            }
-            _ => (),
+        let mut rover = Rover::new(initial_pose.x(), initial_pose.y(), initial_pose.facing());
+        rover.set_battery(battery.clone());
+        rover.set_max_slope(args.max_slope);
+        let rover = match station.place_rover(plateau_id, rover) {
+          Ok(rover) => rover,
+          Err(error) => {
+            eprint!("Failed to create rover: ");
+            return Err(Box::new(error));
           }
+        };
+        if interactive {
+          println!("Created rover with id '{}' and pose '{}'", rover.id(), rover.pose());
         }
-        rover
+        Some(rover)
       }
     };
     dprintln!("rover = {:?}", rover);
 
-    if args.input.is_none() {
+    if interactive {
       println!("Enter the motion path for rover {}:", rover_number + 1);
     }
     // Get the path for the rover and move it accordingly.
-    let motion_vector = match numbered_lines_input.next() {
+    let (mut rover, motion_vector) = match numbered_lines_input.next() {
       Some((n, result)) => match result {
-        Ok(line) if !line.is_empty() => match Motion::parse_path(&line) {
-          Ok(motion_vector) => motion_vector,
-          Err(error) => {
-            eprint!("Error on line {}: \"{}\": ", n + 1, &line);
-            return Err(Box::new(error));
+        Ok(line) if !line.is_empty() || input_format != mission::MissionFormat::Text => {
+          // On a loaded plateau the path may be preceded by the id or name of the rover to move.
+          let (rover, path) = match (rover, line.trim().split_once(char::is_whitespace)) {
+            (Some(rover), _) => (rover, line.as_str()),
+            (None, Some((rover_key, path))) => match station.find_rover(plateau_id, rover_key) {
+              Ok(rover) => (rover, path.trim()),
+              Err(error) => {
+                eprint!("Error on line {}: \"{}\": Failed to load rover '{}': ", n + 1, &line, rover_key);
+                return Err(Box::new(error));
+              }
+            },
+            (None, None) => match station.get_rover_n(rover_number, plateau_id) {
+              Ok(rover) => (rover, line.trim()),
+              Err(_) => break,
+            },
+          };
+          match Motion::parse_path(path) {
+            Ok(motion_vector) => (rover, motion_vector),
+            Err(error) => {
+              eprint!("Error on line {}: \"{}\": ", n + 1, &line);
+              return Err(Box::new(error));
+            }
           }
-        },
-        Ok(_) => return Ok(()),
+        }
+        Ok(_) => break,
         Err(error) => {
           eprint!("Error: ");
           return Err(Box::new(error));
@@ -218,64 +1326,115 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
       None => return Err(Box::new(Error::PathNotFound)),
     };
     dprintln!("path of rover = {:?}", motion_vector);
-    {
-      let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-      rover = match plateau::move_rover(
-        Uuid::from_str(plateau.id()).unwrap(),
-        Uuid::from_str(rover.id()).unwrap(),
-        motion_vector.clone(),
-        &conn,
-      ) {
+    if args.concurrent {
+      // The rovers are moved together once all the paths have been read.
+      trajectories.push(vec![(rover.x(), rover.y())]);
+      moves.push((rover.id().to_string(), motion_vector));
+    } else {
+      let report = match station.move_rover(plateau_id, Uuid::from_str(rover.id()).unwrap(), motion_vector.clone()) {
         Err(error) => {
-          eprint!("Failed to create rover in database: ");
+          eprint!("Failed to move rover in database: ");
           return Err(Box::new(error));
         }
-        Ok(rover) => rover,
+        Ok(report) => report,
+      };
+      rover = report.rover;
+      let trip = report.trip;
+
+      // The machine-readable formats print all the rovers once they have moved.
+      if format == output::OutputFormat::Text {
+        if interactive {
+          print!("Rover {} is now at:", rover_number + 1);
+        }
+        println!("{}{}", rover.pose(), trip);
       }
-    }
 
-    if args.input.is_none() {
-      print!("Rover {} is now at:", rover_number + 1);
+      rovers.push((rover, trip.cost));
+      trajectories.push(trip.trajectory);
     }
-    println!("{}", rover.pose());
 
-    rovers.push(rover);
-
-    match args.plateau {
-      Some(plateau_id) => {
-        let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-        if let Ok(count) = plateau::get_rovers_count(plateau_id, &conn) {
-          if rover_number as usize == count {
-            break;
-          }
+    if args.plateau.is_some() {
+      if let Ok(count) = station.get_rovers_count(plateau_id) {
+        if rover_number as usize + 1 == count {
+          break;
         }
       }
-      None => (),
-    };
+    }
 
     rover_number += 1;
   }
 
-  match args.output {
-    Some(output_path) => match File::create(&output_path) {
-      Ok(mut file) => {
-        for rover in rovers {
-          match file.write_fmt(format_args!("{}\n", rover.pose())) {
-            Ok(_) => (),
-            Err(error) => {
-              eprint!("Failed to write to output file: ",);
-              return Err(Box::new(error));
-            }
-          };
+  if args.concurrent {
+    let rover_ids = moves.iter().map(|(rover_id, _)| rover_id.clone()).collect::<Vec<String>>();
+    let report = match station.simulate_moves(plateau_id, moves, args.on_blocked) {
+      Err(error) => {
+        eprint!("Failed to move rovers: ");
+        return Err(Box::new(error));
+      }
+      Ok(report) => report,
+    };
+    if format == output::OutputFormat::Text {
+      for tick in report.ticks.iter() {
+        println!("{}", tick);
+      }
+    }
+    if let Some(deadlock) = report.deadlock {
+      eprint!("Failed to move rovers: ");
+      return Err(Box::new(Error::DeadlockError(deadlock.to_string())));
+    }
+    if format == output::OutputFormat::Text {
+      for rover in report.rovers.iter() {
+        println!("{}", rover.pose());
+      }
+    }
+    // Every move adds a trajectory, the ticks extend the one of the rover.
+    for tick in report.ticks.iter() {
+      for rover_tick in tick.rovers.iter() {
+        let n = rover_ids.iter().position(|rover_id| rover_id == &rover_tick.rover_id).unwrap();
+        if trajectories[n].last() != Some(&(rover_tick.x, rover_tick.y)) {
+          trajectories[n].push((rover_tick.x, rover_tick.y));
         }
       }
+    }
+    rovers = report
+      .rovers
+      .into_iter()
+      .map(|rover| {
+        let cost = report
+          .ticks
+          .iter()
+          .flat_map(|tick| tick.rovers.iter())
+          .filter(|rover_tick| rover_tick.rover_id == rover.id())
+          .map(|rover_tick| rover_tick.cost)
+          .sum();
+        (rover, cost)
+      })
+      .collect();
+  }
+
+  if args.render {
+    let all_rovers = match station.list_rovers(plateau_id, i64::MAX) {
+      Ok(rovers) => rovers.to_vec(),
+      Err(error) => {
+        eprint!("Failed to read rovers from database: ");
+        return Err(Box::new(error));
+      }
+    };
+    if !args.trajectories {
+      trajectories.clear();
+    }
+    print!("{}", render::ascii(&plateau, &all_rovers, &trajectories));
+  }
+
+  if let Some(format) = args.export {
+    match station.export_plateau(plateau_id, format) {
+      Ok(export) => print!("{}", export),
       Err(error) => {
-        eprint!("Failed to create output file: ",);
+        eprint!("Failed to export plateau: ");
         return Err(Box::new(error));
       }
-    },
-    None => (),
+    }
   }
 
-  Ok(())
+  Ok(Some((plateau, rovers)))
 }
@@ -0,0 +1,308 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::history::HistoryEntry;
+use crate::plateau::{MoveRequest, Plateaus};
+use crate::render::Trajectory;
+use crate::rover::{Blocked, MoveReport, Rovers, Trip};
+use crate::simulation::{BlockedPolicy, SimulationReport};
+use crate::store::Store;
+use crate::{Error, Georeference, Heightmap, Motion, Plateau, PlateauMap, Rover, TerrainMap};
+
+/// A rover moved by the server, along with what happened on the way.
+#[derive(Deserialize)]
+struct MovedRover {
+  #[serde(flatten)]
+  rover: Rover,
+  #[serde(default)]
+  blocked: Vec<Blocked>,
+  #[serde(default)]
+  depleted: Option<usize>,
+  #[serde(default)]
+  cost: i32,
+  #[serde(default)]
+  trajectory: Trajectory,
+}
+
+#[derive(Deserialize)]
+struct DeletedPlateau {
+  rovers: usize,
+}
+
+/// A client of the REST API of `gcs_api`, keeping the plateaus and rovers on the server.
+/// Plateaus and rovers get their ids from the server, so the ones given back replace those sent.
+#[derive(Debug, Clone)]
+pub struct Client {
+  url: String,
+  agent: ureq::Agent,
+}
+
+/// Escapes a plateau or rover key, or a motion path, for a path segment of a URL.
+fn segment(s: &str) -> String {
+  s.bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+      byte => format!("%{:02X}", byte),
+    })
+    .collect()
+}
+
+/// The message of an error response, the `error` field of its JSON body when there is one.
+fn server_error(error: ureq::Error) -> Error {
+  match error {
+    ureq::Error::Status(status, response) => {
+      let status_text = response.status_text().to_string();
+      let message = response
+        .into_json::<Value>()
+        .ok()
+        .and_then(|body| body.get("error").and_then(Value::as_str).map(String::from));
+      Error::ServerError(message.unwrap_or_else(|| format!("{} {}", status, status_text)))
+    }
+    error => Error::ServerError(error.to_string()),
+  }
+}
+
+/// Tells a name already in use, which the server answers with `409 Conflict` when creating, apart from the other errors.
+fn name_taken(error: ureq::Error, name: Option<&str>) -> Error {
+  match (error, name) {
+    (ureq::Error::Status(409, _), Some(name)) => Error::NameTaken(name.into()),
+    (error, _) => server_error(error),
+  }
+}
+
+/// Tells a missing plateau or rover, which the server answers with `404 Not Found`, apart from the other errors.
+fn not_found(error: ureq::Error, missing: impl FnOnce() -> Error) -> Error {
+  match error {
+    ureq::Error::Status(404, _) => missing(),
+    error => server_error(error),
+  }
+}
+
+fn read_json<T: DeserializeOwned>(response: ureq::Response) -> Result<T, Error> {
+  response.into_json().map_err(|error| Error::ServerError(format!("Invalid response: {}", error)))
+}
+
+impl Client {
+  /// A client of the server at `url`, e.g. `http://localhost:9090`.
+  pub fn new(url: &str) -> Self {
+    Self {
+      url: url.trim_end_matches('/').to_string(),
+      agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+    }
+  }
+
+  fn get(&self, path: &str) -> Result<ureq::Response, Error> {
+    self.agent.get(&format!("{}{}", self.url, path)).call().map_err(server_error)
+  }
+
+  fn send_json(&self, method: &str, path: &str, body: impl serde::Serialize) -> Result<ureq::Response, Error> {
+    self
+      .agent
+      .request(method, &format!("{}{}", self.url, path))
+      .send_json(body)
+      .map_err(server_error)
+  }
+
+  /// Sends a request without a body.
+  fn call(&self, method: &str, path: &str) -> Result<ureq::Response, Error> {
+    self.agent.request(method, &format!("{}{}", self.url, path)).call().map_err(server_error)
+  }
+}
+
+impl Store for Client {
+  fn get_plateau(&self, plateau_id: Uuid) -> Result<Plateau, Error> {
+    self.find_plateau(&plateau_id.to_string())
+  }
+
+  fn get_plateau_by_name(&self, name: &str) -> Result<Plateau, Error> {
+    self.find_plateau(name)
+  }
+
+  /// The server looks up a plateau by either its id or its name.
+  fn find_plateau(&self, key: &str) -> Result<Plateau, Error> {
+    let url = format!("{}/plateaus/{}", self.url, segment(key));
+    read_json(
+      self
+        .agent
+        .get(&url)
+        .call()
+        .map_err(|error| not_found(error, || Error::PlateauNotFound(key.into())))?,
+    )
+  }
+
+  fn list_plateaus(&self, total_plateaus: i64) -> Result<Plateaus, Error> {
+    read_json(self.get(&format!("/plateaus?limit={}", total_plateaus))?)
+  }
+
+  fn create_plateau(&self, plateau: Plateau) -> Result<Plateau, Error> {
+    let response = self.agent.post(&format!("{}/plateaus", self.url)).send_json(plateau.to_request());
+    read_json(response.map_err(|error| name_taken(error, plateau.name()))?)
+  }
+
+  /// Creates the plateau of a map, then its rovers one after the other. If one of the rovers can't be created, the
+  /// plateau is deleted again, as far as the server allows it, so that nothing of the map is kept as in the other stores.
+  fn create_map(&self, map: PlateauMap) -> Result<PlateauMap, Error> {
+    let plateau = self.create_plateau(map.plateau)?;
+    let plateau_id = Uuid::from_str(plateau.id()).unwrap();
+    let mut rovers = Vec::new();
+    for rover in map.rovers {
+      match self.create_rover(plateau_id, rover) {
+        Ok(rover) => rovers.push(rover),
+        Err(error) => {
+          let _ = self.delete_plateau(plateau_id);
+          return Err(error);
+        }
+      }
+    }
+    Ok(PlateauMap { plateau, rovers })
+  }
+
+  fn delete_plateau(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    Ok(read_json::<DeletedPlateau>(self.call("DELETE", &format!("/plateaus/{}", plateau_id))?)?.rovers)
+  }
+
+  /// The server has no way to put back a plateau as it was.
+  fn import_plateau(&self, _: Plateau, _: Vec<Rover>, _: Vec<HistoryEntry>) -> Result<(), Error> {
+    Err(Error::ServerError("A plateau can't be put back through the server".into()))
+  }
+
+  fn set_plateau_terrain(&self, plateau_id: Uuid, terrain: TerrainMap) -> Result<Plateau, Error> {
+    read_json(self.send_json("PUT", &format!("/plateaus/{}/terrain", plateau_id), terrain)?)
+  }
+
+  /// The server replaces a heightmap, it doesn't remove one.
+  fn set_plateau_heightmap(&self, plateau_id: Uuid, heightmap: Option<Heightmap>) -> Result<Plateau, Error> {
+    let heightmap = heightmap.ok_or_else(|| Error::ServerError("A heightmap can't be removed through the server".into()))?;
+    let response = self
+      .agent
+      .put(&format!("{}/plateaus/{}/heightmap", self.url, plateau_id))
+      .set("Content-Type", "text/csv")
+      .send_string(&heightmap.to_csv())
+      .map_err(server_error)?;
+    read_json(response)
+  }
+
+  /// The server replaces a georeference, it doesn't remove one.
+  fn set_plateau_georeference(&self, plateau_id: Uuid, georeference: Option<Georeference>) -> Result<Plateau, Error> {
+    let georeference = georeference.ok_or_else(|| Error::ServerError("A georeference can't be removed through the server".into()))?;
+    read_json(self.send_json("PUT", &format!("/plateaus/{}/georeference", plateau_id), georeference)?)
+  }
+
+  fn list_history(&self, plateau_id: Uuid) -> Result<Vec<HistoryEntry>, Error> {
+    read_json(self.get(&format!("/plateaus/{}/history", plateau_id))?)
+  }
+
+  fn get_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<Rover, Error> {
+    self.find_rover(plateau_id, &rover_id.to_string())
+  }
+
+  fn get_rover_by_name(&self, plateau_id: Uuid, name: &str) -> Result<Rover, Error> {
+    self.find_rover(plateau_id, name)
+  }
+
+  /// The server looks up a rover by either its id or its name.
+  fn find_rover(&self, plateau_id: Uuid, key: &str) -> Result<Rover, Error> {
+    let url = format!("{}/plateaus/{}/rovers/{}", self.url, plateau_id, segment(key));
+    read_json(
+      self
+        .agent
+        .get(&url)
+        .call()
+        .map_err(|error| not_found(error, || Error::RoverNotFound(key.into())))?,
+    )
+  }
+
+  fn get_rover_n(&self, n: i64, plateau_id: Uuid) -> Result<Rover, Error> {
+    let missing = || Error::RoverNotFound(format!("{}", n.saturating_add(1)));
+    // A negative `n`, or one past the largest listing, can't be the index of a rover.
+    let index = match usize::try_from(n) {
+      Ok(index) if n < i64::MAX => index,
+      _ => return Err(missing()),
+    };
+    match self.list_rovers(plateau_id, n + 1)?.get(index) {
+      Some(rover) => Ok(rover.clone()),
+      None => Err(missing()),
+    }
+  }
+
+  fn get_rovers_count(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    Ok(self.list_rovers(plateau_id, i64::MAX)?.len())
+  }
+
+  fn list_rovers(&self, plateau_id: Uuid, total_rovers: i64) -> Result<Rovers, Error> {
+    read_json(self.get(&format!("/plateaus/{}/rovers?limit={}", plateau_id, total_rovers))?)
+  }
+
+  fn create_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let response = self
+      .agent
+      .post(&format!("{}/plateaus/{}/rovers", self.url, plateau_id))
+      .send_json(rover.to_request());
+    read_json(response.map_err(|error| name_taken(error, rover.name()))?)
+  }
+
+  /// The server has no way to put a rover back, so moves can't be undone through it.
+  fn restore_rover(&self, _: Uuid, _: Rover) -> Result<Rover, Error> {
+    Err(Error::ServerError("A rover can't be put back through the server".into()))
+  }
+
+  fn delete_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<(), Error> {
+    self.call("DELETE", &format!("/plateaus/{}/rovers/{}", plateau_id, rover_id))?;
+    Ok(())
+  }
+
+  /// The cells the rover went through come with the response, the poses on the way don't.
+  fn move_rover(&self, plateau_id: Uuid, rover_id: Uuid, motion_vector: Vec<Motion>) -> Result<MoveReport, Error> {
+    let path = Motion::to_path(&motion_vector);
+    let moved: MovedRover = read_json(self.call("PATCH", &format!("/plateaus/{}/rovers/{}/{}", plateau_id, rover_id, segment(&path)))?)?;
+    Ok(MoveReport {
+      rover: moved.rover,
+      trip: Trip {
+        blocked: moved.blocked,
+        depleted: moved.depleted,
+        cost: moved.cost,
+        trajectory: moved.trajectory,
+        poses: Vec::new(),
+      },
+    })
+  }
+
+  fn move_group(&self, plateau_id: Uuid, tag: &str, motion_vector: Vec<Motion>) -> Result<Vec<MoveReport>, Error> {
+    let path = Motion::to_path(&motion_vector);
+    read_json(self.call("PATCH", &format!("/plateaus/{}/groups/{}/{}", plateau_id, segment(tag), segment(&path)))?)
+  }
+
+  fn move_rovers(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>) -> Result<Vec<Rover>, Error> {
+    read_json(self.send_json("POST", &format!("/plateaus/{}/moves", plateau_id), move_requests(&moves))?)
+  }
+
+  /// A deadlock is in the report, as the server tells it.
+  fn simulate_moves(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>, policy: BlockedPolicy) -> Result<SimulationReport, Error> {
+    let policy = serde_json::to_value(policy)
+      .ok()
+      .and_then(|policy| policy.as_str().map(String::from))
+      .unwrap_or_default();
+    let url = format!("{}/plateaus/{}/moves?concurrent=true&on_blocked={}", self.url, plateau_id, policy);
+    match self.agent.post(&url).send_json(move_requests(&moves)) {
+      Ok(response) => read_json(response),
+      // A deadlock comes with its report.
+      Err(ureq::Error::Status(409, response)) => read_json(response),
+      Err(error) => Err(server_error(error)),
+    }
+  }
+}
+
+fn move_requests(moves: &[(String, Vec<Motion>)]) -> Vec<MoveRequest> {
+  moves
+    .iter()
+    .map(|(rover_id, motion_vector)| MoveRequest {
+      rover_id: rover_id.clone(),
+      path: Motion::to_path(motion_vector),
+    })
+    .collect()
+}
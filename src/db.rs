@@ -0,0 +1,108 @@
+use std::fmt;
+
+use diesel::{QueryDsl, RunQueryDsl};
+use diesel_migrations::MigrationConnection;
+use serde::Serialize;
+
+use crate::schema::{history, plateaus, rovers};
+use crate::{DBPooledConnection, Error};
+
+/// The migrations of the schema, built into the binaries so that a new database can be set up without them.
+#[allow(dead_code)]
+mod embedded_migrations {
+  use diesel_migrations::EmbedMigrations;
+
+  #[derive(EmbedMigrations)]
+  #[cfg_attr(not(feature = "postgres"), embed_migrations_options(migrations_path = "migrations"))]
+  #[cfg_attr(feature = "postgres", embed_migrations_options(migrations_path = "migrations_postgres"))]
+  struct _Dummy;
+
+  /// The versions of the migrations, oldest first.
+  pub fn versions() -> Vec<String> {
+    ALL_MIGRATIONS.iter().map(|migration| migration.version().to_string()).collect()
+  }
+}
+
+/// The latest version of the schema this build knows.
+pub fn schema_version() -> String {
+  embedded_migrations::versions().pop().unwrap_or_default()
+}
+
+/// Where the schema of the database stands against the migrations of this build.
+#[derive(Debug, Clone)]
+pub struct SchemaStatus {
+  /// The latest migration run on the database, if any.
+  pub version: Option<String>,
+  /// The migrations of this build not run yet, oldest first.
+  pub pending: Vec<String>,
+  /// The migrations run on the database that this build doesn't know, from a later build.
+  pub unknown: Vec<String>,
+}
+
+pub fn schema_status(conn: &DBPooledConnection) -> Result<SchemaStatus, Error> {
+  diesel_migrations::setup_database(&**conn)?;
+  let applied = conn.previously_run_migration_versions()?;
+  let known = embedded_migrations::versions();
+  let mut unknown: Vec<String> = applied.iter().filter(|version| !known.contains(version)).cloned().collect();
+  unknown.sort();
+  Ok(SchemaStatus {
+    version: applied.iter().max().cloned(),
+    pending: known.into_iter().filter(|version| !applied.contains(version)).collect(),
+    unknown,
+  })
+}
+
+/// Runs the migrations the database lacks and gives their versions back. A database migrated by a later build is refused,
+/// this build can't tell what its schema looks like.
+pub fn migrate(conn: &DBPooledConnection) -> Result<Vec<String>, Error> {
+  let status = schema_status(conn)?;
+  if let Some(version) = status.unknown.last() {
+    return Err(Error::SchemaError(format!(
+      "The database schema is at version {}, later than version {} of this build. Upgrade gcs to open this database",
+      version,
+      schema_version()
+    )));
+  }
+  embedded_migrations::run(&**conn).map_err(|error| Error::SchemaError(format!("Failed to migrate the database: {}", error)))?;
+  Ok(status.pending)
+}
+
+/// How many rows the tables of the database hold, and the version of its schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseInfo {
+  pub plateaus: i64,
+  pub rovers: i64,
+  pub history: i64,
+  pub schema_version: Option<String>,
+  pub pending_migrations: usize,
+}
+
+/// A database never migrated has no tables to count yet.
+pub fn info(conn: &DBPooledConnection) -> Result<DatabaseInfo, Error> {
+  let status = schema_status(conn)?;
+  let (plateaus, rovers, history) = match status.version {
+    Some(_) => (
+      plateaus::table.count().get_result(conn)?,
+      rovers::table.count().get_result(conn)?,
+      history::table.count().get_result(conn)?,
+    ),
+    None => (0, 0, 0),
+  };
+  Ok(DatabaseInfo {
+    plateaus,
+    rovers,
+    history,
+    schema_version: status.version,
+    pending_migrations: status.pending.len(),
+  })
+}
+
+impl fmt::Display for DatabaseInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "Plateaus: {}", self.plateaus)?;
+    writeln!(f, "Rovers: {}", self.rovers)?;
+    writeln!(f, "History entries: {}", self.history)?;
+    writeln!(f, "Schema version: {}", self.schema_version.as_deref().unwrap_or("none"))?;
+    write!(f, "Pending migrations: {}", self.pending_migrations)
+  }
+}
@@ -3,7 +3,10 @@ use std::fmt;
 use diesel_derive_enum::DbEnum;
 use serde::{Deserialize, Serialize};
 
+/// Kept as the lowercase name of the direction: text in SQLite, the `direction` enum type in PostgreSQL.
 #[derive(Debug, Clone, Serialize, Deserialize, DbEnum)]
+#[PgType = "direction"]
+#[DieselType = "DirectionMapping"]
 pub enum Direction {
   North,
   East,
@@ -9,6 +9,30 @@ pub enum Error {
   CoOrdinateParseError(String),
   PoseParseError(String),
   MotionParseError(String),
+  NameParseError(String),
+  RoverNotFound(String),
+  CollisionError(String),
+  PolicyParseError(String),
+  DeadlockError(String),
+  TerrainParseError(String),
+  HeightmapParseError(String),
+  MapParseError(String),
+  GeoreferenceError(String),
+  FormatParseError(String),
+  MissionParseError(String),
+  InputParseError(String),
+  CommandParseError(String),
+  PlateauNotFound(String),
+  NameTaken(String),
+  ServerError(String),
+  SchemaError(String),
+  BackupError(String),
+  SnapshotParseError(String),
+  ReplayError(String),
+  RenderError(String),
+  BatteryError(String),
+  ConnectionError(String),
+  DatabaseError(diesel::result::Error),
 }
 
 impl fmt::Display for Error {
@@ -20,6 +44,30 @@ impl fmt::Display for Error {
       Self::CoOrdinateParseError(error) => write!(f, "Error parsing co-ordinate: {}", error),
       Self::PoseParseError(error) => write!(f, "Error parsing pose: {}", error),
       Self::MotionParseError(error) => write!(f, "Error parsing motion: {}", error),
+      Self::NameParseError(error) => write!(f, "Error parsing name: {}", error),
+      Self::RoverNotFound(rover) => write!(f, "Error: Rover '{}' not found", rover),
+      Self::CollisionError(error) => write!(f, "Error: Collision: {}", error),
+      Self::PolicyParseError(error) => write!(f, "Error parsing blocked policy: {}", error),
+      Self::DeadlockError(error) => write!(f, "Error: Rovers stopped by a {}", error),
+      Self::TerrainParseError(error) => write!(f, "Error parsing terrain: {}", error),
+      Self::HeightmapParseError(error) => write!(f, "Error parsing heightmap: {}", error),
+      Self::MapParseError(error) => write!(f, "Error parsing map: {}", error),
+      Self::GeoreferenceError(error) => write!(f, "Error in georeference: {}", error),
+      Self::FormatParseError(error) => write!(f, "Error parsing format: {}", error),
+      Self::MissionParseError(error) => write!(f, "Error parsing mission: {}", error),
+      Self::InputParseError(error) => write!(f, "Error parsing input: {}", error),
+      Self::CommandParseError(error) => write!(f, "Error parsing command: {}", error),
+      Self::PlateauNotFound(plateau) => write!(f, "Error: Plateau '{}' not found", plateau),
+      Self::NameTaken(name) => write!(f, "Error: Name '{}' is already taken", name),
+      Self::ServerError(error) => write!(f, "Error from server: {}", error),
+      Self::SchemaError(error) => write!(f, "Error in database schema: {}", error),
+      Self::BackupError(error) => write!(f, "Error in database backup: {}", error),
+      Self::SnapshotParseError(error) => write!(f, "Error parsing snapshot: {}", error),
+      Self::ReplayError(error) => write!(f, "Error in replay: {}", error),
+      Self::RenderError(error) => write!(f, "Error rendering plateau: {}", error),
+      Self::BatteryError(error) => write!(f, "Error in battery: {}", error),
+      Self::ConnectionError(error) => write!(f, "Error connecting to database: {}", error),
+      Self::DatabaseError(error) => write!(f, "Error accessing database: {}", error),
     }
   }
 }
@@ -30,4 +78,10 @@ impl From<ParseIntError> for Error {
   }
 }
 
+impl From<diesel::result::Error> for Error {
+  fn from(error: diesel::result::Error) -> Self {
+    Self::DatabaseError(error)
+  }
+}
+
 impl std::error::Error for Error {}
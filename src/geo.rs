@@ -0,0 +1,233 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::render::{escape, Trajectory};
+use crate::{Error, Plateau, Rover};
+
+/// The mean radius of the Earth, in metres.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Where a plateau lies on the Earth.
+///
+/// The origin is the south-west corner of cell `0 0`, every cell is a square of `cell_size` metres and the plateau is
+/// turned `rotation` degrees clockwise, i.e. its north (the `y` axis) points to that bearing.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Georeference {
+  pub latitude: f64,
+  pub longitude: f64,
+  pub cell_size: f64,
+  #[serde(default)]
+  pub rotation: f64,
+}
+
+impl Georeference {
+  pub fn new(latitude: f64, longitude: f64, cell_size: f64, rotation: f64) -> Result<Self, Error> {
+    let georeference = Self {
+      latitude,
+      longitude,
+      cell_size,
+      rotation,
+    };
+    georeference.check()?;
+    Ok(georeference)
+  }
+
+  /// Checks that the origin is on the Earth, away from the poles, and that the cells have a size.
+  pub fn check(&self) -> Result<(), Error> {
+    if self.latitude.is_nan() || self.latitude.abs() >= 90.0 {
+      return Err(Error::GeoreferenceError(format!("Latitude {} isn't between -90 and 90", self.latitude)));
+    }
+    if self.longitude.is_nan() || self.longitude.abs() > 180.0 {
+      return Err(Error::GeoreferenceError(format!("Longitude {} isn't between -180 and 180", self.longitude)));
+    }
+    if self.cell_size.is_nan() || self.cell_size <= 0.0 || self.cell_size.is_infinite() {
+      return Err(Error::GeoreferenceError(format!("Cell size {} isn't a positive length", self.cell_size)));
+    }
+    if !self.rotation.is_finite() {
+      return Err(Error::GeoreferenceError(format!("Invalid rotation {}", self.rotation)));
+    }
+    Ok(())
+  }
+
+  /// The longitude and latitude of a point of the plateau, `x` and `y` being counted in cells from the origin.
+  ///
+  /// The plateau is small enough for the Earth to be taken as flat around it: the offset from the origin in metres is
+  /// turned by the rotation, then divided by the length of a degree of latitude and of longitude at the origin.
+  pub fn to_lon_lat(&self, x: f64, y: f64) -> (f64, f64) {
+    let (sin, cos) = self.rotation.to_radians().sin_cos();
+    let east = (x * cos + y * sin) * self.cell_size;
+    let north = (y * cos - x * sin) * self.cell_size;
+    let latitude = self.latitude + (north / EARTH_RADIUS).to_degrees();
+    let longitude = self.longitude + (east / (EARTH_RADIUS * self.latitude.to_radians().cos())).to_degrees();
+    (round(longitude), round(latitude))
+  }
+
+  /// The longitude and latitude of the center of a cell.
+  pub fn cell_center(&self, x: i32, y: i32) -> (f64, f64) {
+    self.to_lon_lat(f64::from(x) + 0.5, f64::from(y) + 0.5)
+  }
+
+  /// The corners of the rectangle from cell `x0 y0` to cell `x1 y1`, counter-clockwise from the south-west corner,
+  /// the first corner being repeated at the end to close the ring.
+  pub fn rectangle(&self, (x0, y0): (i32, i32), (x1, y1): (i32, i32)) -> Vec<(f64, f64)> {
+    let (x0, y0, x1, y1) = (f64::from(x0), f64::from(y0), f64::from(x1) + 1.0, f64::from(y1) + 1.0);
+    vec![
+      self.to_lon_lat(x0, y0),
+      self.to_lon_lat(x1, y0),
+      self.to_lon_lat(x1, y1),
+      self.to_lon_lat(x0, y1),
+      self.to_lon_lat(x0, y0),
+    ]
+  }
+}
+
+/// Rounds a coordinate to 7 decimals, about a centimetre.
+fn round(degrees: f64) -> f64 {
+  (degrees * 1e7).round() / 1e7
+}
+
+/// Reads `LAT,LON,CELL_SIZE` or `LAT,LON,CELL_SIZE,ROTATION`.
+impl FromStr for Georeference {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let fields = s
+      .split(',')
+      .map(|field| field.trim().parse::<f64>())
+      .collect::<Result<Vec<f64>, _>>()
+      .map_err(|_| Error::GeoreferenceError(format!("Expected LAT,LON,CELL_SIZE[,ROTATION] but found '{}'", s)))?;
+    match fields[..] {
+      [latitude, longitude, cell_size] => Self::new(latitude, longitude, cell_size, 0.0),
+      [latitude, longitude, cell_size, rotation] => Self::new(latitude, longitude, cell_size, rotation),
+      _ => Err(Error::GeoreferenceError(format!("Expected LAT,LON,CELL_SIZE[,ROTATION] but found '{}'", s))),
+    }
+  }
+}
+
+/// The formats a georeferenced plateau can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  GeoJson,
+  Kml,
+}
+
+impl FromStr for ExportFormat {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "geojson" => Ok(Self::GeoJson),
+      "kml" => Ok(Self::Kml),
+      other => Err(Error::FormatParseError(format!("Expected geojson or kml but found '{}'", other))),
+    }
+  }
+}
+
+fn georeference(plateau: &Plateau) -> Result<&Georeference, Error> {
+  plateau
+    .georeference()
+    .ok_or_else(|| Error::GeoreferenceError(format!("Plateau {} has no georeference", plateau.label())))
+}
+
+/// Exports the plateau, its obstacles, the trajectories of the rovers and the rovers themselves, `trajectories[n]`
+/// being the trajectory of `rovers[n]`.
+pub fn export(format: ExportFormat, plateau: &Plateau, rovers: &[Rover], trajectories: &[Trajectory]) -> Result<String, Error> {
+  match format {
+    ExportFormat::GeoJson => geojson(plateau, rovers, trajectories),
+    ExportFormat::Kml => kml(plateau, rovers, trajectories),
+  }
+}
+
+fn positions(points: &[(f64, f64)]) -> Value {
+  points.iter().map(|(longitude, latitude)| json!([longitude, latitude])).collect()
+}
+
+/// A GeoJSON feature collection, every feature telling its `kind` in its properties.
+pub fn geojson(plateau: &Plateau, rovers: &[Rover], trajectories: &[Trajectory]) -> Result<String, Error> {
+  let georeference = georeference(plateau)?;
+  let mut features = vec![json!({
+    "type": "Feature",
+    "geometry": {"type": "Polygon", "coordinates": [positions(&georeference.rectangle((0, 0), (plateau.x_max(), plateau.y_max())))]},
+    "properties": {"kind": "plateau", "id": plateau.id(), "name": plateau.name()},
+  })];
+  for (x, y) in plateau.terrain().impassable_cells() {
+    features.push(json!({
+      "type": "Feature",
+      "geometry": {"type": "Polygon", "coordinates": [positions(&georeference.rectangle((x, y), (x, y)))]},
+      "properties": {"kind": "obstacle", "x": x, "y": y},
+    }));
+  }
+  for (rover, trajectory) in rovers.iter().zip(trajectories).filter(|(_, trajectory)| trajectory.len() > 1) {
+    let points = trajectory.iter().map(|(x, y)| georeference.cell_center(*x, *y)).collect::<Vec<(f64, f64)>>();
+    features.push(json!({
+      "type": "Feature",
+      "geometry": {"type": "LineString", "coordinates": positions(&points)},
+      "properties": {"kind": "trajectory", "rover_id": rover.id()},
+    }));
+  }
+  for rover in rovers {
+    let (longitude, latitude) = georeference.cell_center(rover.x(), rover.y());
+    features.push(json!({
+      "type": "Feature",
+      "geometry": {"type": "Point", "coordinates": [longitude, latitude]},
+      "properties": {"kind": "rover", "id": rover.id(), "name": rover.name(), "x": rover.x(), "y": rover.y(), "facing": rover.facing()},
+    }));
+  }
+  let collection = json!({"type": "FeatureCollection", "features": features});
+  Ok(serde_json::to_string_pretty(&collection).unwrap_or_default() + "\n")
+}
+
+fn kml_coordinates(points: &[(f64, f64)]) -> String {
+  points
+    .iter()
+    .map(|(longitude, latitude)| format!("{},{},0", longitude, latitude))
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+fn kml_polygon(kml: &mut String, name: &str, points: &[(f64, f64)]) {
+  let _ = writeln!(kml, "<Placemark>");
+  let _ = writeln!(kml, "<name>{}</name>", escape(name));
+  let _ = writeln!(
+    kml,
+    "<Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon>",
+    kml_coordinates(points)
+  );
+  let _ = writeln!(kml, "</Placemark>");
+}
+
+/// A KML document with the plateau and its obstacles as polygons, the trajectories as lines and the rovers as points.
+pub fn kml(plateau: &Plateau, rovers: &[Rover], trajectories: &[Trajectory]) -> Result<String, Error> {
+  let georeference = georeference(plateau)?;
+  let mut kml = String::new();
+  kml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+  let _ = writeln!(kml, "<name>Plateau {}</name>", escape(plateau.label()));
+  kml_polygon(&mut kml, "Plateau", &georeference.rectangle((0, 0), (plateau.x_max(), plateau.y_max())));
+  for (x, y) in plateau.terrain().impassable_cells() {
+    kml_polygon(&mut kml, &format!("Obstacle {} {}", x, y), &georeference.rectangle((x, y), (x, y)));
+  }
+  for (rover, trajectory) in rovers.iter().zip(trajectories).filter(|(_, trajectory)| trajectory.len() > 1) {
+    let points = trajectory.iter().map(|(x, y)| georeference.cell_center(*x, *y)).collect::<Vec<(f64, f64)>>();
+    let _ = writeln!(kml, "<Placemark>");
+    let _ = writeln!(kml, "<name>Trajectory of {}</name>", escape(rover.label()));
+    let _ = writeln!(kml, "<LineString><coordinates>{}</coordinates></LineString>", kml_coordinates(&points));
+    let _ = writeln!(kml, "</Placemark>");
+  }
+  for rover in rovers {
+    let _ = writeln!(kml, "<Placemark>");
+    let _ = writeln!(kml, "<name>{}</name>", escape(rover.label()));
+    let _ = writeln!(kml, "<description>{} {} {}</description>", rover.x(), rover.y(), rover.facing());
+    let _ = writeln!(
+      kml,
+      "<Point><coordinates>{}</coordinates></Point>",
+      kml_coordinates(&[georeference.cell_center(rover.x(), rover.y())])
+    );
+    let _ = writeln!(kml, "</Placemark>");
+  }
+  kml.push_str("</Document>\n</kml>\n");
+  Ok(kml)
+}
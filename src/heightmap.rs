@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::plateau::MAX_BOUND;
+use crate::Error;
+
+/// The height of every cell of a plateau, as rows of heights from the top row (`y_max`) down to row 0,
+/// the way a grid file lists them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Heightmap(Vec<Vec<i32>>);
+
+impl Heightmap {
+  pub fn new(rows: Vec<Vec<i32>>) -> Result<Self, Error> {
+    let heightmap = Self(rows);
+    heightmap.check()?;
+    Ok(heightmap)
+  }
+
+  /// Writes one row of comma separated heights per line, as `from_csv` reads them.
+  pub fn to_csv(&self) -> String {
+    self
+      .0
+      .iter()
+      .map(|row| row.iter().map(|height| height.to_string()).collect::<Vec<String>>().join(",") + "\n")
+      .collect()
+  }
+
+  /// Reads a PGM image, plain (`P2`) or raw (`P5`), or a CSV grid otherwise.
+  pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+    match bytes.starts_with(b"P2") || bytes.starts_with(b"P5") {
+      true => Self::from_pgm(bytes),
+      false => match std::str::from_utf8(bytes) {
+        Ok(text) => Self::from_csv(text),
+        Err(_) => Err(Error::HeightmapParseError("Expected a PGM image or a CSV grid".into())),
+      },
+    }
+  }
+
+  /// Reads one row of comma separated heights per line, blank lines being skipped.
+  pub fn from_csv(text: &str) -> Result<Self, Error> {
+    let mut rows = Vec::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+      let row = line.split(',').map(|height| height.trim().parse::<i32>()).collect::<Result<Vec<i32>, _>>();
+      match row {
+        Ok(row) => rows.push(row),
+        Err(_) => return Err(Error::HeightmapParseError(format!("Invalid height on line {}", n + 1))),
+      }
+    }
+    Self::new(rows)
+  }
+
+  /// Reads a PGM image, the gray value of every pixel being the height of a cell.
+  pub fn from_pgm(bytes: &[u8]) -> Result<Self, Error> {
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 4 {
+      // Whitespace and comments may come between any two fields of the header.
+      while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+        if bytes[pos] == b'#' {
+          while pos < bytes.len() && bytes[pos] != b'\n' {
+            pos += 1;
+          }
+        } else {
+          pos += 1;
+        }
+      }
+      let start = pos;
+      while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+      }
+      if start == pos {
+        return Err(Error::HeightmapParseError("Incomplete PGM header".into()));
+      }
+      header.push(String::from_utf8_lossy(&bytes[start..pos]).to_string());
+    }
+    let size = header[1..]
+      .iter()
+      .map(|field| field.parse::<usize>())
+      .collect::<Result<Vec<usize>, _>>()
+      .map_err(|_| Error::HeightmapParseError("Invalid PGM header".into()))?;
+    let (width, height, max_value) = (size[0], size[1], size[2]);
+    // A heightmap can't be larger than the largest plateau, whatever the header says.
+    let side = MAX_BOUND as usize + 1;
+    if width > side || height > side {
+      return Err(Error::HeightmapParseError(format!(
+        "Expected at most {}x{} pixels but the PGM header gives {}x{}",
+        side, side, width, height
+      )));
+    }
+    let pixel_count = width
+      .checked_mul(height)
+      .ok_or_else(|| Error::HeightmapParseError("Invalid PGM header".into()))?;
+
+    let values = match header[0].as_str() {
+      "P2" => String::from_utf8_lossy(&bytes[pos..])
+        .split_whitespace()
+        .map(|value| value.parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| Error::HeightmapParseError("Invalid PGM pixel".into()))?,
+      // A single whitespace character separates the header from the pixels.
+      _ => {
+        let pixels = bytes.get(pos + 1..).unwrap_or_default();
+        match max_value < 256 {
+          true => pixels.iter().map(|value| i32::from(*value)).collect(),
+          false => pixels
+            .chunks_exact(2)
+            .map(|value| i32::from(u16::from_be_bytes([value[0], value[1]])))
+            .collect(),
+        }
+      }
+    };
+    if width == 0 || values.len() < pixel_count {
+      return Err(Error::HeightmapParseError(format!(
+        "Expected {} pixels but found {}",
+        pixel_count,
+        values.len()
+      )));
+    }
+    Self::new(values.chunks(width).take(height).map(|row| row.to_vec()).collect())
+  }
+
+  pub fn width(&self) -> usize {
+    self.0.first().map(|row| row.len()).unwrap_or_default()
+  }
+
+  pub fn height(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn rows(&self) -> &[Vec<i32>] {
+    &self.0
+  }
+
+  /// The height of a cell, `None` outside the heightmap.
+  pub fn get(&self, x: i32, y: i32) -> Option<i32> {
+    let row = self.height().checked_sub(1 + usize::try_from(y).ok()?)?;
+    self.0.get(row)?.get(usize::try_from(x).ok()?).copied()
+  }
+
+  /// Checks that the heightmap isn't empty and that all its rows are as long.
+  pub fn check(&self) -> Result<(), Error> {
+    if self.width() == 0 {
+      return Err(Error::HeightmapParseError("Empty heightmap".into()));
+    }
+    match self.0.iter().position(|row| row.len() != self.width()) {
+      Some(n) => Err(Error::HeightmapParseError(format!(
+        "Expected {} heights on row {} but found {}",
+        self.width(),
+        n + 1,
+        self.0[n].len()
+      ))),
+      None => Ok(()),
+    }
+  }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::render::Trajectory;
+use crate::schema::history;
+use crate::{DBPooledConnection, Direction, Pose, Rover};
+
+/// A command carried out by a rover of a plateau: its creation, or a motion path with the pose of the rover after every step.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryEntry {
+  pub id: i32,
+  pub rover_id: String,
+  /// The commands of a batch were carried out together, in lock step.
+  pub batch: String,
+  pub created_at: DateTime<Utc>,
+  /// The motion path, empty for the creation of the rover.
+  pub command: String,
+  /// The pose of the rover before the command.
+  pub start: Pose,
+  pub poses: Vec<Pose>,
+}
+
+#[derive(Queryable)]
+struct HistoryEntryDB {
+  id: i32,
+  _plateau_id: String,
+  rover_id: String,
+  batch: String,
+  created_at: NaiveDateTime,
+  command: String,
+  x: i32,
+  y: i32,
+  facing: Direction,
+  poses: String,
+}
+
+#[derive(Insertable)]
+#[table_name = "history"]
+struct NewHistoryEntryDB<'a> {
+  plateau_id: &'a str,
+  rover_id: &'a str,
+  batch: &'a str,
+  created_at: NaiveDateTime,
+  command: &'a str,
+  x: i32,
+  y: i32,
+  facing: Direction,
+  poses: String,
+}
+
+impl HistoryEntryDB {
+  fn to_history_entry(&self) -> HistoryEntry {
+    HistoryEntry {
+      id: self.id,
+      rover_id: self.rover_id.clone(),
+      batch: self.batch.clone(),
+      created_at: Utc.from_utc_datetime(&self.created_at),
+      command: self.command.clone(),
+      start: Pose::new(self.x, self.y, self.facing.clone()),
+      poses: serde_json::from_str(&self.poses).unwrap_or_default(),
+    }
+  }
+}
+
+/// Starts a batch of commands carried out together.
+pub(crate) fn new_batch() -> String {
+  Uuid::new_v4().to_hyphenated().to_string()
+}
+
+/// Records a command of a rover, `start` being its pose before the command and `poses` its pose after every step.
+pub(crate) fn record(
+  _plateau_id: &str,
+  _rover_id: &str,
+  _batch: &str,
+  _command: &str,
+  start: &Pose,
+  _poses: &[Pose],
+  conn: &DBPooledConnection,
+) -> Result<(), diesel::result::Error> {
+  let entry = NewHistoryEntryDB {
+    plateau_id: _plateau_id,
+    rover_id: _rover_id,
+    batch: _batch,
+    created_at: Utc::now().naive_utc(),
+    command: _command,
+    x: start.x(),
+    y: start.y(),
+    facing: start.facing(),
+    poses: serde_json::to_string(_poses).unwrap_or_else(|_| "[]".into()),
+  };
+  diesel::insert_into(history::table).values(&entry).execute(conn)?;
+  Ok(())
+}
+
+/// Puts back the commands of a plateau as they were, e.g. from a snapshot. The entries get new ids, in the same order.
+pub(crate) fn import(_plateau_id: &str, entries: &[HistoryEntry], conn: &DBPooledConnection) -> Result<(), diesel::result::Error> {
+  for entry in entries {
+    let entry = NewHistoryEntryDB {
+      plateau_id: _plateau_id,
+      rover_id: &entry.rover_id,
+      batch: &entry.batch,
+      created_at: entry.created_at.naive_utc(),
+      command: &entry.command,
+      x: entry.start.x(),
+      y: entry.start.y(),
+      facing: entry.start.facing(),
+      poses: serde_json::to_string(&entry.poses).unwrap_or_else(|_| "[]".into()),
+    };
+    diesel::insert_into(history::table).values(&entry).execute(conn)?;
+  }
+  Ok(())
+}
+
+/// The commands carried out on a plateau, oldest first.
+pub fn list_history(_plateau_id: Uuid, conn: &DBPooledConnection) -> Result<Vec<HistoryEntry>, diesel::result::Error> {
+  use crate::schema::history::dsl::*;
+
+  let entries = history
+    .filter(plateau_id.eq(_plateau_id.to_string()))
+    .order(id.asc())
+    .load::<HistoryEntryDB>(conn)?;
+
+  Ok(entries.iter().map(|entry| entry.to_history_entry()).collect())
+}
+
+/// Steps through the history of a plateau, one frame per step of the rovers.
+/// A frame holds every rover created so far, in its pose at that time. The rovers of a batch step together.
+///
+/// The rovers are taken from `rovers` when they can be found there, for their names.
+pub fn frames(entries: &[HistoryEntry], rovers: &[Rover]) -> Vec<Vec<Rover>> {
+  let mut state: Vec<Rover> = Vec::new();
+  let mut frames = Vec::new();
+  for batch in entries.chunk_by(|entry, next| entry.batch == next.batch) {
+    for entry in batch {
+      let rover = match state.iter_mut().find(|rover| rover.id() == entry.rover_id) {
+        Some(rover) => rover,
+        None => {
+          let rover = rover_or_new(rovers, &entry.rover_id, &entry.start);
+          state.push(rover);
+          state.last_mut().unwrap()
+        }
+      };
+      rover.set_pose(&entry.start);
+    }
+    let steps = batch.iter().map(|entry| entry.poses.len()).max().unwrap_or_default();
+    if steps == 0 {
+      frames.push(state.clone());
+    }
+    for step in 0..steps {
+      for entry in batch {
+        if let Some(pose) = entry.poses.get(step) {
+          state.iter_mut().find(|rover| rover.id() == entry.rover_id).unwrap().set_pose(pose);
+        }
+      }
+      frames.push(state.clone());
+    }
+  }
+  frames
+}
+
+/// The cells every rover went through in the history, `trajectories[n]` being the trajectory of `rovers[n]`.
+pub fn trajectories(entries: &[HistoryEntry], rovers: &[Rover]) -> Vec<Trajectory> {
+  rovers
+    .iter()
+    .map(|rover| {
+      let mut trajectory = Trajectory::new();
+      for entry in entries.iter().filter(|entry| entry.rover_id == rover.id()) {
+        for pose in std::iter::once(&entry.start).chain(entry.poses.iter()) {
+          if trajectory.last() != Some(&(pose.x(), pose.y())) {
+            trajectory.push((pose.x(), pose.y()));
+          }
+        }
+      }
+      trajectory
+    })
+    .collect()
+}
+
+fn rover_or_new(rovers: &[Rover], rover_id: &str, pose: &Pose) -> Rover {
+  match rovers.iter().find(|rover| rover.id() == rover_id) {
+    Some(rover) => rover.clone(),
+    None => Rover::with_id(rover_id, pose.x(), pose.y(), pose.facing()),
+  }
+}
@@ -0,0 +1,460 @@
+use std::fmt;
+
+use crate::plateau::MAX_BOUND;
+use crate::{labels, Error, Terrain};
+
+/// An error in the text input, pointing at the line and column it was found at.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  /// The line, from 1.
+  pub line: usize,
+  /// The column of the first character in error, from 1.
+  pub column: usize,
+  /// The number of characters in error, at least 1.
+  pub length: usize,
+  pub message: String,
+  source: String,
+}
+
+impl fmt::Display for Diagnostic {
+  /// Writes the error with the line it was found on and a caret under the characters in error.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let gutter = " ".repeat(self.line.to_string().len());
+    writeln!(f, "error: {}", self.message)?;
+    writeln!(f, "{}--> line {}, column {}", gutter, self.line, self.column)?;
+    writeln!(f, "{} |", gutter)?;
+    // Tabs are shown as single spaces so that the caret lines up with the characters in error.
+    writeln!(f, "{} | {}", self.line, self.source.replace('\t', " "))?;
+    writeln!(f, "{} | {}{}", gutter, " ".repeat(self.column - 1), "^".repeat(self.length))
+  }
+}
+
+/// Every error found in the text input, in the order of the lines.
+#[derive(Debug, Clone)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+  pub fn summary(&self) -> String {
+    match self.0.len() {
+      1 => "1 error in the input".into(),
+      n => format!("{} errors in the input", n),
+    }
+  }
+}
+
+impl fmt::Display for Diagnostics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for diagnostic in self.0.iter() {
+      writeln!(f, "{}", diagnostic)?;
+    }
+    write!(f, "{}", self.summary())
+  }
+}
+
+/// What the lines of the text input hold, which depends on where the plateau and its rovers come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+  /// The plateau bounds, an optional terrain block, then the initial pose and the motion path of every rover.
+  /// Several plateaus may follow each other, each in a section starting with a `PLATEAU` line.
+  NewPlateau,
+  /// The motion path of every rover of a map, in order.
+  MapPaths,
+  /// The motion paths of the rovers of a loaded plateau, each optionally preceded by the id or name of the rover.
+  LoadedPlateau,
+}
+
+/// A word of a line, with the column of its first character, from 1.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+  text: &'a str,
+  column: usize,
+}
+
+impl Token<'_> {
+  fn len(&self) -> usize {
+    self.text.chars().count()
+  }
+}
+
+/// Splits a line into words at any whitespace, a `#` starting a comment that runs to the end of the line.
+fn tokenize(line: &str) -> Vec<Token<'_>> {
+  let code = match line.find('#') {
+    Some(comment) => &line[..comment],
+    None => line,
+  };
+  let mut tokens = Vec::new();
+  let mut start = None;
+  for (column, (offset, c)) in code.char_indices().enumerate() {
+    match (start, c.is_whitespace()) {
+      (None, false) => start = Some((offset, column + 1)),
+      (Some((begin, first)), true) => {
+        tokens.push(Token {
+          text: &code[begin..offset],
+          column: first,
+        });
+        start = None;
+      }
+      _ => (),
+    }
+  }
+  if let Some((begin, first)) = start {
+    tokens.push(Token {
+      text: &code[begin..],
+      column: first,
+    });
+  }
+  tokens
+}
+
+/// Whether a word is made of motions only, which may be lowercase.
+fn is_path(word: &str) -> bool {
+  word.chars().all(|motion| matches!(motion.to_ascii_uppercase(), 'L' | 'R' | 'M'))
+}
+
+/// The word starting a section of the input, and the line introducing the plateau of the section in the output.
+pub const SECTION: &str = "PLATEAU";
+
+fn is_section(tokens: &[Token]) -> bool {
+  tokens.first().is_some_and(|token| token.text.eq_ignore_ascii_case(SECTION))
+}
+
+struct Parser<'a> {
+  lines: Vec<&'a str>,
+  /// The index of the next line to read.
+  next: usize,
+  diagnostics: Vec<Diagnostic>,
+  /// The lines read, rewritten the way the line-based readers of `gcs_cli` expect them, with their index.
+  output: Vec<(usize, String)>,
+}
+
+impl<'a> Parser<'a> {
+  /// The next line holding more than whitespace and comments, with its index and words.
+  fn next_line(&mut self) -> Option<(usize, Vec<Token<'a>>)> {
+    while self.next < self.lines.len() {
+      let n = self.next;
+      self.next += 1;
+      let tokens = tokenize(self.lines[n]);
+      if !tokens.is_empty() {
+        return Some((n, tokens));
+      }
+    }
+    None
+  }
+
+  fn peek_line(&mut self) -> Option<(usize, Vec<Token<'a>>)> {
+    let next = self.next;
+    let line = self.next_line();
+    self.next = next;
+    line
+  }
+
+  fn error(&mut self, n: usize, column: usize, length: usize, message: String) {
+    self.diagnostics.push(Diagnostic {
+      line: n + 1,
+      column,
+      length: length.max(1),
+      message,
+      source: self.lines.get(n).copied().unwrap_or_default().to_string(),
+    });
+  }
+
+  /// Reports an error just past the last word of a line, for something missing at its end.
+  fn error_at_end(&mut self, n: usize, tokens: &[Token], message: String) {
+    let column = tokens.last().map(|token| token.column + token.len() + 1).unwrap_or(1);
+    self.error(n, column, 1, message);
+  }
+
+  /// Reports the words left after what a line should hold.
+  fn error_on_rest(&mut self, n: usize, rest: &[Token], what: &str) {
+    if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+      let text = rest.iter().map(|token| token.text).collect::<Vec<&str>>().join(" ");
+      self.error(
+        n,
+        first.column,
+        last.column + last.len() - first.column,
+        format!("Unexpected '{}' after the {}", text, what),
+      );
+    }
+  }
+
+  fn integer(&mut self, n: usize, token: &Token, what: &str, non_negative: bool) -> Option<i32> {
+    match token.text.parse::<i32>() {
+      Ok(value) if !non_negative || value >= 0 => Some(value),
+      _ => {
+        let kind = if non_negative { "a non-negative integer" } else { "an integer" };
+        self.error(
+          n,
+          token.column,
+          token.len(),
+          format!("Expected {} for the {} but found '{}'", kind, what, token.text),
+        );
+        None
+      }
+    }
+  }
+
+  /// Reads a `PLATEAU` line, which may name the plateau of the section it starts.
+  fn section(&mut self, n: usize, tokens: &[Token]) {
+    self.error_on_rest(n, &tokens[tokens.len().min(2)..], "plateau name");
+    match tokens.get(1) {
+      Some(name) => {
+        if let Err(Error::NameParseError(message)) = labels::validate_name(name.text) {
+          self.error(n, name.column, name.len(), message);
+        }
+        self.output.push((n, format!("{} {}", SECTION, name.text)));
+      }
+      None => self.output.push((n, SECTION.into())),
+    }
+  }
+
+  /// Reads a plateau: its bounds, its terrain block if there is one and its rovers, up to the next section.
+  fn plateau(&mut self, after: Option<usize>) {
+    let bounds = match (self.peek_line(), after) {
+      (Some((n, tokens)), _) if !is_section(&tokens) => {
+        self.next_line();
+        self.bounds(n, &tokens)
+      }
+      (_, Some(n)) => {
+        self.error_at_end(n, &tokenize(self.lines[n]), "Expected the plateau bounds after the section".into());
+        return;
+      }
+      (_, None) => {
+        self.error(0, 1, 1, "Expected the plateau bounds but the input is empty".into());
+        return;
+      }
+    };
+    if let Some((n, tokens)) = self.peek_line() {
+      if tokens.len() == 1 && tokens[0].text.eq_ignore_ascii_case("TERRAIN") {
+        self.next_line();
+        self.output.push((n, "TERRAIN".into()));
+        self.terrain(bounds);
+      }
+    }
+    while let Some((n, tokens)) = self.peek_line() {
+      if is_section(&tokens) {
+        break;
+      }
+      self.next_line();
+      self.pose(n, &tokens);
+      match self.next_line() {
+        Some((m, tokens)) => {
+          let path = self.path(m, &tokens);
+          self.output.push((m, path));
+        }
+        None => self.error_at_end(n, &tokens, format!("Expected a motion path for the rover on line {}", n + 1)),
+      }
+    }
+  }
+
+  fn bounds(&mut self, n: usize, tokens: &[Token]) -> Option<(i32, i32)> {
+    if tokens.len() < 2 {
+      self.error_at_end(n, tokens, "Expected the plateau bounds, x_max and y_max, but the y_max is missing".into());
+      return None;
+    }
+    self.error_on_rest(n, &tokens[2..], "plateau bounds");
+    let x_max = self.bound(n, &tokens[0], "x_max");
+    let y_max = self.bound(n, &tokens[1], "y_max");
+    let bounds = x_max.zip(y_max)?;
+    self.output.push((n, format!("{} {}", bounds.0, bounds.1)));
+    Some(bounds)
+  }
+
+  /// Reads a plateau bound, which can't be larger than [`MAX_BOUND`], so that the number of cells fits.
+  fn bound(&mut self, n: usize, token: &Token, what: &str) -> Option<i32> {
+    let bound = self.integer(n, token, &format!("{} of the plateau", what), true)?;
+    if bound > MAX_BOUND {
+      self.error(
+        n,
+        token.column,
+        token.len(),
+        format!("Expected at most {} for the {} of the plateau but found {}", MAX_BOUND, what, bound),
+      );
+      return None;
+    }
+    Some(bound)
+  }
+
+  /// Reads the rows of a terrain block, one per line, a comment being allowed after the row.
+  fn terrain(&mut self, bounds: Option<(i32, i32)>) {
+    let mut rows = 0;
+    while bounds.is_none_or(|(_, y_max)| rows < y_max + 1) && self.next < self.lines.len() {
+      let n = self.next;
+      let line = self.lines[n];
+      let indent = line.len() - line.trim_start().len();
+      let row = match line[indent..].split_whitespace().next() {
+        Some(row) => row,
+        None => {
+          self.next += 1;
+          continue;
+        }
+      };
+      // Without bounds the block ends at the first line that isn't a row of terrain symbols.
+      if bounds.is_none() && !row.chars().all(|symbol| Terrain::from_symbol(symbol).is_some()) {
+        break;
+      }
+      self.next += 1;
+      rows += 1;
+      let column = line[..indent].chars().count() + 1;
+      for (offset, symbol) in row.chars().enumerate() {
+        if Terrain::from_symbol(symbol).is_none() {
+          self.error(n, column + offset, 1, format!("Unknown terrain '{}', expected '.', 's', 'i' or '#'", symbol));
+        }
+      }
+      let length = row.chars().count();
+      if let Some((x_max, _)) = bounds {
+        if length as i32 != x_max + 1 {
+          self.error(
+            n,
+            column,
+            length,
+            format!("Expected {} cells in the terrain row but found {}", x_max + 1, length),
+          );
+        }
+      }
+      let rest = tokenize(&line[indent + row.len()..]);
+      if let (Some(first), Some(last)) = (rest.first(), rest.last()) {
+        let (shift, text) = (column + length - 1, rest.iter().map(|token| token.text).collect::<Vec<&str>>().join(" "));
+        self.error(
+          n,
+          shift + first.column,
+          last.column + last.len() - first.column,
+          format!("Unexpected '{}' after the terrain row", text),
+        );
+      }
+      self.output.push((n, row.to_string()));
+    }
+    if let Some((_, y_max)) = bounds {
+      if rows < y_max + 1 {
+        let n = self.lines.len().saturating_sub(1);
+        self.error(n, 1, 1, format!("Expected {} terrain rows but found {}", y_max + 1, rows));
+      }
+    }
+  }
+
+  fn pose(&mut self, n: usize, tokens: &[Token]) {
+    if tokens.len() < 3 {
+      let missing = ["x co-ordinate", "y co-ordinate", "heading"][tokens.len()];
+      self.error_at_end(n, tokens, format!("Expected a pose, x y heading, but the {} is missing", missing));
+      return;
+    }
+    self.error_on_rest(n, &tokens[3..], "pose");
+    let x = self.integer(n, &tokens[0], "x co-ordinate of the rover", false);
+    let y = self.integer(n, &tokens[1], "y co-ordinate of the rover", false);
+    let heading = match tokens[2].text {
+      "N" | "n" | "E" | "e" | "S" | "s" | "W" | "w" => Some(tokens[2].text.to_ascii_uppercase()),
+      other => {
+        self.error(
+          n,
+          tokens[2].column,
+          tokens[2].len(),
+          format!("Expected a heading, N, E, S or W, but found '{}'", other),
+        );
+        None
+      }
+    };
+    if let (Some(x), Some(y), Some(heading)) = (x, y, heading) {
+      self.output.push((n, format!("{} {} {}", x, y, heading)));
+    }
+  }
+
+  /// Reads a motion path, which may be split into several words, reporting every run of invalid motions.
+  fn path(&mut self, n: usize, tokens: &[Token]) -> String {
+    let mut path = String::new();
+    for token in tokens {
+      let mut invalid: Option<(usize, String)> = None;
+      for (offset, motion) in token.text.chars().enumerate() {
+        match (motion.to_ascii_uppercase(), &mut invalid) {
+          ('L' | 'R' | 'M', _) => {
+            if let Some((column, motions)) = invalid.take() {
+              self.invalid_motions(n, column, &motions);
+            }
+            path.push(motion.to_ascii_uppercase());
+          }
+          (_, Some((_, motions))) => motions.push(motion),
+          (_, None) => invalid = Some((token.column + offset, motion.to_string())),
+        }
+      }
+      if let Some((column, motions)) = invalid {
+        self.invalid_motions(n, column, &motions);
+      }
+    }
+    path
+  }
+
+  fn invalid_motions(&mut self, n: usize, column: usize, motions: &str) {
+    let message = match motions.chars().count() {
+      1 => format!("Invalid motion '{}', expected L, R or M", motions),
+      _ => format!("Invalid motions '{}', expected L, R or M", motions),
+    };
+    self.error(n, column, motions.chars().count(), message);
+  }
+}
+
+/// Reads the whole text input, reporting every error found in it rather than stopping at the first one.
+///
+/// Words may be separated by any whitespace, headings and motions may be lowercase, blank lines are skipped
+/// and a `#` starts a comment that runs to the end of the line, but in the rows of a terrain block, where it stands
+/// for an impassable cell. The lines are given back with their index, rewritten the way the line-based readers expect.
+pub fn parse(text: &str, layout: Layout) -> Result<Vec<(usize, String)>, Diagnostics> {
+  let mut parser = Parser {
+    lines: text.lines().collect(),
+    next: 0,
+    diagnostics: Vec::new(),
+    output: Vec::new(),
+  };
+  match layout {
+    Layout::NewPlateau => {
+      // Without a `PLATEAU` line first, the input starts with the bounds of its only or first plateau.
+      let mut after = None;
+      loop {
+        if let Some((n, tokens)) = parser.peek_line().filter(|(_, tokens)| is_section(tokens)) {
+          parser.next_line();
+          parser.section(n, &tokens);
+          after = Some(n);
+        }
+        parser.plateau(after);
+        if parser.peek_line().is_none() {
+          break;
+        }
+      }
+    }
+    Layout::MapPaths => {
+      while let Some((n, tokens)) = parser.next_line() {
+        let path = parser.path(n, &tokens);
+        parser.output.push((n, path));
+      }
+    }
+    Layout::LoadedPlateau => {
+      while let Some((n, tokens)) = parser.next_line() {
+        // A single word is a motion path, more words are the rover followed by its path. A first word made of
+        // motions could be either, e.g. `LM LM`, so it is rejected rather than guessed.
+        match tokens.split_first() {
+          Some((rover, path)) if !path.is_empty() && is_path(rover.text) => parser.error(
+            n,
+            rover.column,
+            rover.len(),
+            format!(
+              "Ambiguous '{}', which reads as a rover or as motions: write the path as one word or give the rover by its id",
+              rover.text
+            ),
+          ),
+          Some((rover, path)) if !path.is_empty() => {
+            let path = parser.path(n, path);
+            parser.output.push((n, format!("{} {}", rover.text, path)));
+          }
+          _ => {
+            let path = parser.path(n, &tokens);
+            parser.output.push((n, path));
+          }
+        }
+      }
+    }
+  }
+  match parser.diagnostics.is_empty() {
+    true => Ok(parser.output),
+    false => {
+      parser.diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+      Err(Diagnostics(parser.diagnostics))
+    }
+  }
+}
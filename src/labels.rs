@@ -0,0 +1,46 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::Error;
+
+pub type Tags = BTreeSet<String>;
+
+/// Names address plateaus and rovers wherever an id is accepted, so they must
+/// not look like an id and must survive being a single path segment or word.
+pub fn validate_name(name: &str) -> Result<(), Error> {
+  if name.is_empty() {
+    return Err(Error::NameParseError("Name can't be empty".into()));
+  }
+  if Uuid::from_str(name).is_ok() {
+    return Err(Error::NameParseError(format!("Name '{}' can't be a UUID", name)));
+  }
+  if let Some(c) = name.chars().find(|c| c.is_whitespace() || *c == '/') {
+    return Err(Error::NameParseError(format!("Name '{}' contains invalid character {:?}", name, c)));
+  }
+  Ok(())
+}
+
+pub(crate) fn tags_to_db(tags: &Tags) -> String {
+  serde_json::to_string(tags).unwrap_or_else(|_| "[]".into())
+}
+
+pub(crate) fn tags_from_db(tags: &str) -> Tags {
+  serde_json::from_str(tags).unwrap_or_default()
+}
+
+pub(crate) fn metadata_to_db(metadata: &Value) -> Option<String> {
+  match metadata {
+    Value::Null => None,
+    metadata => Some(metadata.to_string()),
+  }
+}
+
+pub(crate) fn metadata_from_db(metadata: &Option<String>) -> Value {
+  match metadata {
+    Some(metadata) => serde_json::from_str(metadata).unwrap_or(Value::Null),
+    None => Value::Null,
+  }
+}
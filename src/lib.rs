@@ -1,31 +1,66 @@
+// diesel 1.x derives and `table!` expand to impls nested in consts.
+#![allow(non_local_definitions)]
+
 #[macro_use]
 extern crate actix_web;
 #[macro_use]
 extern crate diesel;
 
+pub mod backup;
+mod battery;
+pub mod client;
+pub mod db;
 mod direction;
 mod error;
+pub mod geo;
+mod heightmap;
+pub mod history;
+pub mod input;
+mod labels;
+mod map;
+pub mod memory;
+pub mod mission;
 mod motion;
+pub mod output;
 pub mod plateau;
 mod pose;
+pub mod render;
+pub mod repl;
 pub mod rover;
 mod schema;
+pub mod simulation;
+pub mod snapshot;
+pub mod store;
+mod terrain;
+pub mod tui;
 
 use std::time::Duration;
 
-use diesel::SqliteConnection;
 use diesel::{connection::SimpleConnection, r2d2::ConnectionManager, r2d2::CustomizeConnection};
 use r2d2::{Pool, PooledConnection};
 
+pub use battery::{Battery, BatteryRequest};
 pub use direction::Direction;
 pub use error::Error;
+pub use geo::Georeference;
+pub use heightmap::Heightmap;
+pub use labels::Tags;
+pub use map::PlateauMap;
 pub use motion::Motion;
 pub use plateau::Plateau;
 pub use pose::Pose;
 pub use rover::Rover;
+pub use simulation::Simulation;
+pub use terrain::{Terrain, TerrainMap};
+
+/// The connection to the database: SQLite, or PostgreSQL with the `postgres` feature.
+#[cfg(not(feature = "postgres"))]
+pub type DBConnection = diesel::SqliteConnection;
+#[cfg(feature = "postgres")]
+pub type DBConnection = diesel::PgConnection;
 
-pub type DBPool = Pool<ConnectionManager<SqliteConnection>>;
-pub type DBPooledConnection = PooledConnection<ConnectionManager<SqliteConnection>>;
+pub type DBPool = Pool<ConnectionManager<DBConnection>>;
+pub type DBPooledConnection = PooledConnection<ConnectionManager<DBConnection>>;
 
 pub const APPLICATION_JSON: &str = "application/json";
 pub const CONNECTION_POOL_ERROR: &str = "couldn't get DB connection from pool";
@@ -51,8 +86,9 @@ pub struct ConnectionOptions {
   pub busy_timeout: Option<Duration>,
 }
 
-impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOptions {
-  fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+#[cfg(not(feature = "postgres"))]
+impl CustomizeConnection<DBConnection, diesel::r2d2::Error> for ConnectionOptions {
+  fn on_acquire(&self, conn: &mut DBConnection) -> Result<(), diesel::r2d2::Error> {
     (|| {
       if self.enable_wal {
         conn.batch_execute("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
@@ -68,3 +104,16 @@ impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionOp
     .map_err(diesel::r2d2::Error::QueryError)
   }
 }
+
+/// PostgreSQL always keeps a write-ahead log and checks foreign keys, only the timeout applies.
+#[cfg(feature = "postgres")]
+impl CustomizeConnection<DBConnection, diesel::r2d2::Error> for ConnectionOptions {
+  fn on_acquire(&self, conn: &mut DBConnection) -> Result<(), diesel::r2d2::Error> {
+    match self.busy_timeout {
+      Some(d) => conn
+        .batch_execute(&format!("SET lock_timeout = {};", d.as_millis()))
+        .map_err(diesel::r2d2::Error::QueryError),
+      None => Ok(()),
+    }
+  }
+}
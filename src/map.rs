@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::plateau::MAX_BOUND;
+use crate::{Direction, Error, Plateau, Rover, Terrain, TerrainMap};
+
+/// A plateau with its obstacles and rovers, drawn as a grid of characters from the top row (`y_max`) down to row 0:
+/// * `.` is a free cell and `#` an obstacle, i.e. an impassable cell,
+/// * `^`, `>`, `v` and `<` are rovers facing north, east, south and west,
+/// * a digit is a numbered rover, facing north unless a legend line after the grid gives its heading, e.g. `1 >` or `1 E`.
+///
+/// The numbered rovers come first, by number, followed by the arrows in reading order.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PlateauMap {
+  pub plateau: Plateau,
+  pub rovers: Vec<Rover>,
+}
+
+fn parse_heading(s: &str) -> Option<Direction> {
+  match s {
+    "^" | "N" => Some(Direction::North),
+    ">" | "E" => Some(Direction::East),
+    "v" | "S" => Some(Direction::South),
+    "<" | "W" => Some(Direction::West),
+    _ => None,
+  }
+}
+
+impl FromStr for PlateauMap {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    // The grid ends at the first blank line or line with whitespace in it, the legend follows.
+    let mut lines = s.lines().map(|line| line.trim_end()).skip_while(|line| line.is_empty()).peekable();
+    let mut rows = Vec::new();
+    while let Some(line) = lines.next_if(|line| !line.is_empty() && !line.contains(char::is_whitespace)) {
+      rows.push(line.chars().collect::<Vec<char>>());
+    }
+    if rows.is_empty() {
+      return Err(Error::MapParseError("Empty map".into()));
+    }
+    let width = rows[0].len();
+    if let Some(n) = rows.iter().position(|row| row.len() != width) {
+      return Err(Error::MapParseError(format!(
+        "Expected {} cells on line {} but found {}",
+        width,
+        n + 1,
+        rows[n].len()
+      )));
+    }
+    let size = MAX_BOUND as usize + 1;
+    if width > size || rows.len() > size {
+      return Err(Error::MapParseError(format!(
+        "Expected at most {} cells by {} rows but found {} by {}",
+        size,
+        size,
+        width,
+        rows.len()
+      )));
+    }
+
+    let mut headings = BTreeMap::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+      let legend = line.split_whitespace().collect::<Vec<&str>>();
+      match legend[..] {
+        [number, heading] if number.len() == 1 && number.chars().all(|c| c.is_ascii_digit()) => match parse_heading(heading) {
+          Some(heading) => headings.insert(number.chars().next().unwrap(), heading),
+          None => return Err(Error::MapParseError(format!("Invalid heading '{}' for rover {}", heading, number))),
+        },
+        _ => return Err(Error::MapParseError(format!("Expected a rover number and heading but found '{}'", line.trim()))),
+      };
+    }
+
+    let (x_max, y_max) = (width as i32 - 1, rows.len() as i32 - 1);
+    let mut terrain = TerrainMap::new();
+    let mut numbered = BTreeMap::new();
+    let mut arrows = Vec::new();
+    for (n, row) in rows.iter().enumerate() {
+      let y = y_max - n as i32;
+      for (x, cell) in row.iter().enumerate() {
+        let x = x as i32;
+        match cell {
+          '.' => (),
+          '#' => terrain.insert(x, y, Terrain::Impassable),
+          digit if digit.is_ascii_digit() => {
+            let facing = headings.remove(digit).unwrap_or(Direction::North);
+            if numbered.insert(*digit, (x, y, facing)).is_some() {
+              return Err(Error::MapParseError(format!("Rover {} appears more than once", digit)));
+            }
+          }
+          arrow => match parse_heading(&arrow.to_string()) {
+            Some(facing) if !arrow.is_ascii_uppercase() => arrows.push((x, y, facing)),
+            _ => return Err(Error::MapParseError(format!("Unknown cell '{}' at {} {}", arrow, x, y))),
+          },
+        }
+      }
+    }
+    if let Some(number) = headings.keys().next() {
+      return Err(Error::MapParseError(format!("Rover {} isn't on the map", number)));
+    }
+
+    let mut plateau = Plateau::new(x_max, y_max);
+    plateau.set_terrain(terrain)?;
+    Ok(Self {
+      plateau,
+      rovers: numbered.into_values().chain(arrows).map(|(x, y, facing)| Rover::new(x, y, facing)).collect(),
+    })
+  }
+}
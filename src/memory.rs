@@ -0,0 +1,347 @@
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::history::{self, HistoryEntry};
+use crate::plateau::{self, Plateaus};
+use crate::rover::{MoveReport, Rovers};
+use crate::simulation::{BlockedPolicy, SimulationReport};
+use crate::store::Store;
+use crate::{Error, Georeference, Heightmap, Motion, Plateau, PlateauMap, Pose, Rover, TerrainMap};
+
+/// Plateaus, rovers and their history kept in memory, so that missions can be run without a database and leave
+/// nothing behind.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  tables: Mutex<Tables>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Tables {
+  /// The plateaus, oldest first.
+  plateaus: Vec<Plateau>,
+  /// The rovers along with the id of their plateau, oldest first.
+  rovers: Vec<(String, Rover)>,
+  /// The commands carried out, along with the id of their plateau, oldest first.
+  history: Vec<(String, HistoryEntry)>,
+  /// The id of the latest history entry. Like the ids of the database, an id isn't given again once its entry is
+  /// deleted.
+  last_history_id: i32,
+}
+
+impl Tables {
+  fn next_history_id(&mut self) -> i32 {
+    self.last_history_id += 1;
+    self.last_history_id
+  }
+
+  fn record(&mut self, plateau_id: &str, rover_id: &str, batch: &str, command: &str, start: &Pose, poses: &[Pose]) {
+    let entry = HistoryEntry {
+      id: self.next_history_id(),
+      rover_id: rover_id.to_string(),
+      batch: batch.to_string(),
+      created_at: Utc::now(),
+      command: command.to_string(),
+      start: start.clone(),
+      poses: poses.to_vec(),
+    };
+    self.history.push((plateau_id.to_string(), entry));
+  }
+
+  fn plateau(&self, plateau_id: Uuid) -> Result<Plateau, Error> {
+    match self.plateaus.iter().find(|plateau| plateau.id() == plateau_id.to_string()) {
+      Some(plateau) => Ok(plateau.clone()),
+      None => Err(Error::PlateauNotFound(plateau_id.to_string())),
+    }
+  }
+
+  fn update_plateau(&mut self, plateau: &Plateau) {
+    if let Some(kept) = self.plateaus.iter_mut().find(|kept| kept.id() == plateau.id()) {
+      *kept = plateau.clone();
+    }
+  }
+
+  /// The rovers of a plateau, oldest first.
+  fn rovers(&self, plateau_id: Uuid) -> Vec<Rover> {
+    let plateau_id = plateau_id.to_string();
+    self.rovers.iter().filter(|(id, _)| *id == plateau_id).map(|(_, rover)| rover.clone()).collect()
+  }
+
+  /// Writes back rovers that were moved.
+  fn update_rover(&mut self, rover: &Rover) {
+    if let Some((_, kept)) = self.rovers.iter_mut().find(|(_, kept)| kept.id() == rover.id()) {
+      *kept = rover.clone();
+    }
+  }
+
+  fn create_plateau(&mut self, plateau: Plateau) -> Result<Plateau, Error> {
+    if let Some(name) = plateau.name() {
+      if self.plateaus.iter().any(|other| other.name() == Some(name)) {
+        return Err(Error::NameTaken(name.into()));
+      }
+    }
+    self.plateaus.push(plateau.clone());
+    Ok(plateau)
+  }
+
+  fn create_rover(&mut self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let plateau = self.plateau(plateau_id)?;
+    if let Some(name) = rover.name() {
+      if self.rovers(plateau_id).iter().any(|other| other.name() == Some(name)) {
+        return Err(Error::NameTaken(name.into()));
+      }
+    }
+    self.rovers.push((plateau.id().to_string(), rover.clone()));
+    self.record(plateau.id(), rover.id(), &history::new_batch(), "", &rover.pose(), &[]);
+    Ok(rover)
+  }
+}
+
+impl MemoryStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn tables(&self) -> MutexGuard<'_, Tables> {
+    self.tables.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+impl Store for MemoryStore {
+  fn get_plateau(&self, plateau_id: Uuid) -> Result<Plateau, Error> {
+    self.tables().plateau(plateau_id)
+  }
+
+  fn get_plateau_by_name(&self, name: &str) -> Result<Plateau, Error> {
+    match self.tables().plateaus.iter().find(|plateau| plateau.name() == Some(name)) {
+      Some(plateau) => Ok(plateau.clone()),
+      None => Err(Error::PlateauNotFound(name.into())),
+    }
+  }
+
+  fn list_plateaus(&self, total_plateaus: i64) -> Result<Plateaus, Error> {
+    Ok(Plateaus(
+      self.tables().plateaus.iter().rev().take(total_plateaus.max(0) as usize).cloned().collect(),
+    ))
+  }
+
+  fn create_plateau(&self, plateau: Plateau) -> Result<Plateau, Error> {
+    self.tables().create_plateau(plateau)
+  }
+
+  fn create_map(&self, map: PlateauMap) -> Result<PlateauMap, Error> {
+    let mut tables = self.tables();
+    // The map is created on a copy of the tables, kept only once everything is created.
+    let mut created = tables.clone();
+    let plateau = created.create_plateau(map.plateau)?;
+    let plateau_id = Uuid::from_str(plateau.id()).unwrap();
+    let mut rovers = Vec::new();
+    for rover in map.rovers {
+      rovers.push(created.create_rover(plateau_id, rover)?);
+    }
+    *tables = created;
+    Ok(PlateauMap { plateau, rovers })
+  }
+
+  fn delete_plateau(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    let mut tables = self.tables();
+    let plateau = tables.plateau(plateau_id)?;
+    let rovers = tables.rovers(plateau_id).len();
+    tables.plateaus.retain(|other| other.id() != plateau.id());
+    tables.rovers.retain(|(id, _)| id != plateau.id());
+    tables.history.retain(|(id, _)| id != plateau.id());
+    Ok(rovers)
+  }
+
+  fn import_plateau(&self, plateau: Plateau, rovers: Vec<Rover>, history: Vec<HistoryEntry>) -> Result<(), Error> {
+    let mut tables = self.tables();
+    let mut imported = tables.clone();
+    imported.plateaus.retain(|other| other.id() != plateau.id());
+    imported.rovers.retain(|(id, _)| id != plateau.id());
+    imported.history.retain(|(id, _)| id != plateau.id());
+    imported.create_plateau(plateau.clone())?;
+    for rover in rovers {
+      imported.rovers.push((plateau.id().to_string(), rover));
+    }
+    for entry in history {
+      let id = imported.next_history_id();
+      imported.history.push((plateau.id().to_string(), HistoryEntry { id, ..entry }));
+    }
+    *tables = imported;
+    Ok(())
+  }
+
+  fn set_plateau_terrain(&self, plateau_id: Uuid, terrain: TerrainMap) -> Result<Plateau, Error> {
+    let mut tables = self.tables();
+    let mut plateau = tables.plateau(plateau_id)?;
+    plateau.set_terrain(terrain)?;
+    tables.update_plateau(&plateau);
+    Ok(plateau)
+  }
+
+  fn set_plateau_heightmap(&self, plateau_id: Uuid, heightmap: Option<Heightmap>) -> Result<Plateau, Error> {
+    let mut tables = self.tables();
+    let mut plateau = tables.plateau(plateau_id)?;
+    plateau.set_heightmap(heightmap)?;
+    tables.update_plateau(&plateau);
+    Ok(plateau)
+  }
+
+  fn set_plateau_georeference(&self, plateau_id: Uuid, georeference: Option<Georeference>) -> Result<Plateau, Error> {
+    let mut tables = self.tables();
+    let mut plateau = tables.plateau(plateau_id)?;
+    plateau.set_georeference(georeference)?;
+    tables.update_plateau(&plateau);
+    Ok(plateau)
+  }
+
+  fn list_history(&self, plateau_id: Uuid) -> Result<Vec<HistoryEntry>, Error> {
+    let plateau_id = plateau_id.to_string();
+    Ok(
+      self
+        .tables()
+        .history
+        .iter()
+        .filter(|(id, _)| *id == plateau_id)
+        .map(|(_, entry)| entry.clone())
+        .collect(),
+    )
+  }
+
+  fn get_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<Rover, Error> {
+    match self.tables().rovers(plateau_id).into_iter().find(|rover| rover.id() == rover_id.to_string()) {
+      Some(rover) => Ok(rover),
+      None => Err(Error::RoverNotFound(rover_id.to_string())),
+    }
+  }
+
+  fn get_rover_by_name(&self, plateau_id: Uuid, name: &str) -> Result<Rover, Error> {
+    match self.tables().rovers(plateau_id).into_iter().find(|rover| rover.name() == Some(name)) {
+      Some(rover) => Ok(rover),
+      None => Err(Error::RoverNotFound(name.into())),
+    }
+  }
+
+  fn get_rover_n(&self, n: i64, plateau_id: Uuid) -> Result<Rover, Error> {
+    let rover = usize::try_from(n).ok().and_then(|n| self.tables().rovers(plateau_id).into_iter().rev().nth(n));
+    rover.ok_or_else(|| Error::RoverNotFound(format!("{}", n.saturating_add(1))))
+  }
+
+  fn get_rovers_count(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    Ok(self.tables().rovers(plateau_id).len())
+  }
+
+  fn list_rovers(&self, plateau_id: Uuid, total_rovers: i64) -> Result<Rovers, Error> {
+    Ok(Rovers::new(
+      self.tables().rovers(plateau_id).into_iter().rev().take(total_rovers.max(0) as usize).collect(),
+    ))
+  }
+
+  fn create_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    self.tables().create_rover(plateau_id, rover)
+  }
+
+  fn restore_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let mut tables = self.tables();
+    let current = match tables.rovers(plateau_id).into_iter().find(|current| current.id() == rover.id()) {
+      Some(current) => current,
+      None => return Err(Error::RoverNotFound(rover.id().into())),
+    };
+    tables.update_rover(&rover);
+    tables.record(
+      &plateau_id.to_string(),
+      rover.id(),
+      &history::new_batch(),
+      "undo",
+      &current.pose(),
+      &[rover.pose()],
+    );
+    Ok(rover)
+  }
+
+  fn delete_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<(), Error> {
+    let mut tables = self.tables();
+    let plateau_id = plateau_id.to_string();
+    let rover_id = rover_id.to_string();
+    if !tables.rovers.iter().any(|(id, rover)| *id == plateau_id && rover.id() == rover_id) {
+      return Err(Error::RoverNotFound(rover_id));
+    }
+    tables.rovers.retain(|(_, rover)| rover.id() != rover_id);
+    tables.history.retain(|(_, entry)| entry.rover_id != rover_id);
+    Ok(())
+  }
+
+  fn move_rover(&self, plateau_id: Uuid, rover_id: Uuid, motion_vector: Vec<Motion>) -> Result<MoveReport, Error> {
+    let mut tables = self.tables();
+    let plateau = tables.plateau(plateau_id)?;
+    let mut rover = match tables.rovers(plateau_id).into_iter().find(|rover| rover.id() == rover_id.to_string()) {
+      Some(rover) => rover,
+      None => return Err(Error::RoverNotFound(rover_id.to_string())),
+    };
+    let (start, report) = plateau::move_single(&plateau, &mut rover, &motion_vector);
+    tables.update_rover(&report.rover);
+    tables.record(
+      plateau.id(),
+      report.rover.id(),
+      &history::new_batch(),
+      &Motion::to_path(&motion_vector),
+      &start,
+      &report.trip.poses,
+    );
+    Ok(report)
+  }
+
+  fn move_group(&self, plateau_id: Uuid, tag: &str, motion_vector: Vec<Motion>) -> Result<Vec<MoveReport>, Error> {
+    let mut tables = self.tables();
+    let plateau = tables.plateau(plateau_id)?;
+    let mut rovers = tables.rovers(plateau_id);
+    let mut reports = Vec::new();
+    for (start, report) in plateau::move_tagged(&plateau, &mut rovers, tag, &motion_vector) {
+      tables.update_rover(&report.rover);
+      tables.record(
+        plateau.id(),
+        report.rover.id(),
+        &history::new_batch(),
+        &Motion::to_path(&motion_vector),
+        &start,
+        &report.trip.poses,
+      );
+      reports.push(report);
+    }
+    match reports.is_empty() {
+      true => Err(Error::RoverNotFound(tag.into())),
+      false => Ok(reports),
+    }
+  }
+
+  fn move_rovers(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>) -> Result<Vec<Rover>, Error> {
+    let mut tables = self.tables();
+    let plateau = tables.plateau(plateau_id)?;
+    let mut rovers = tables.rovers(plateau_id);
+    let mut moved = Vec::new();
+    for (start, command, poses, rover) in plateau::move_in_turn(&plateau, &mut rovers, moves)? {
+      tables.update_rover(&rover);
+      tables.record(plateau.id(), rover.id(), &history::new_batch(), &command, &start, &poses);
+      moved.push(rover);
+    }
+    Ok(moved)
+  }
+
+  fn simulate_moves(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>, policy: BlockedPolicy) -> Result<SimulationReport, Error> {
+    let mut tables = self.tables();
+    let plateau = tables.plateau(plateau_id)?;
+    let (report, commands) = plateau::simulate(&plateau, tables.rovers(plateau_id), moves, policy)?;
+    if report.deadlock.is_none() {
+      for rover in report.rovers.iter() {
+        tables.update_rover(rover);
+      }
+      let batch = history::new_batch();
+      for command in commands.iter() {
+        tables.record(plateau.id(), &command.rover_id, &batch, &command.path, &command.start, &command.poses);
+      }
+    }
+    Ok(report)
+  }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+
+use crate::plateau::{Cells, PlateauRequest};
+use crate::rover::RoverRequest;
+use crate::{Error, Motion, PlateauMap, Terrain};
+
+/// The formats `gcs_cli` reads a mission from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissionFormat {
+  /// The plateau bounds, then an initial pose and a motion path line for every rover.
+  #[default]
+  Text,
+  Json,
+  Yaml,
+  Toml,
+}
+
+impl MissionFormat {
+  /// Tells the format from the extension of a file, text when it isn't `.json`, `.yaml`, `.yml` or `.toml`.
+  pub fn from_path(path: &Path) -> Self {
+    match path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(|extension| extension.to_ascii_lowercase())
+      .as_deref()
+    {
+      Some("json") => Self::Json,
+      Some("yaml" | "yml") => Self::Yaml,
+      Some("toml") => Self::Toml,
+      _ => Self::Text,
+    }
+  }
+}
+
+impl FromStr for MissionFormat {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      "yaml" => Ok(Self::Yaml),
+      "toml" => Ok(Self::Toml),
+      other => Err(Error::FormatParseError(format!("Expected text, json, yaml or toml but found '{}'", other))),
+    }
+  }
+}
+
+/// A rover of a mission with the motion path it follows, which may be left out for a rover that stays put.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MissionRover {
+  #[serde(flatten)]
+  pub rover: RoverRequest,
+  #[serde(default)]
+  pub path: String,
+}
+
+/// A mission document: the plateau as the REST API takes it, its obstacles and the rovers with their motion paths,
+/// which are carried out in order. In YAML:
+///
+/// ```yaml
+/// plateau: {x_max: 5, y_max: 5, name: mars}
+/// obstacles: [[2, 2]]
+/// rovers:
+///   - {name: spirit, x: 1, y: 2, facing: North, path: LMLMLMLMM}
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Mission {
+  pub plateau: PlateauRequest,
+  #[serde(default)]
+  pub obstacles: Cells,
+  #[serde(default)]
+  pub rovers: Vec<MissionRover>,
+}
+
+/// Several missions in one document, under a `missions` key, each on its own plateau.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Missions {
+  pub missions: Vec<Mission>,
+}
+
+/// Only tells whether a document holds several missions.
+#[derive(Deserialize)]
+struct MissionsKey {
+  missions: Option<IgnoredAny>,
+}
+
+fn from_str<T: DeserializeOwned>(s: &str, format: MissionFormat) -> Result<T, Error> {
+  match format {
+    MissionFormat::Json => serde_json::from_str(s).map_err(|error| Error::MissionParseError(error.to_string())),
+    MissionFormat::Yaml => serde_yaml::from_str(s).map_err(|error| Error::MissionParseError(error.to_string())),
+    MissionFormat::Toml => toml::from_str(s).map_err(|error| Error::MissionParseError(error.to_string())),
+    MissionFormat::Text => Err(Error::MissionParseError("The text format is read line by line".into())),
+  }
+}
+
+impl Mission {
+  /// Reads a structured mission document, the text format being read line by line by `gcs_cli` instead.
+  pub fn parse(s: &str, format: MissionFormat) -> Result<Self, Error> {
+    from_str(s, format)
+  }
+
+  /// Reads a structured document holding either a single mission or a list of them under a `missions` key.
+  pub fn parse_all(s: &str, format: MissionFormat) -> Result<Vec<Self>, Error> {
+    match from_str::<MissionsKey>(s, format)?.missions {
+      Some(_) => Ok(from_str::<Missions>(s, format)?.missions),
+      None => Ok(vec![Self::parse(s, format)?]),
+    }
+  }
+
+  /// The plateau with its obstacles and rovers, and the motion path of every rover in the same order.
+  pub fn to_map(&self) -> Result<(PlateauMap, Vec<String>), Error> {
+    let mut plateau = self.plateau.to_plateau()?;
+    let mut terrain = plateau.terrain().clone();
+    for (x, y) in self.obstacles.iter() {
+      terrain.insert(*x, *y, Terrain::Impassable);
+    }
+    plateau.set_terrain(terrain)?;
+
+    let mut rovers = Vec::new();
+    let mut occupied = HashSet::new();
+    for (n, mission_rover) in self.rovers.iter().enumerate() {
+      let rover = mission_rover.rover.to_rover()?;
+      let label = rover.name().map(|name| name.to_string()).unwrap_or_else(|| format!("{}", n + 1));
+      if !plateau.contains(rover.x(), rover.y()) {
+        return Err(Error::MissionParseError(format!(
+          "Rover {} at {} {} is outside the plateau",
+          label,
+          rover.x(),
+          rover.y()
+        )));
+      }
+      if !plateau.is_passable(rover.x(), rover.y()) {
+        return Err(Error::MissionParseError(format!(
+          "Rover {} at {} {} is on an obstacle",
+          label,
+          rover.x(),
+          rover.y()
+        )));
+      }
+      if !occupied.insert((rover.x(), rover.y())) {
+        return Err(Error::MissionParseError(format!(
+          "Rover {} at {} {} is on another rover",
+          label,
+          rover.x(),
+          rover.y()
+        )));
+      }
+      if let Err(error) = Motion::parse_path(mission_rover.path.trim()) {
+        return Err(Error::MissionParseError(format!("Path of rover {}: {}", label, error)));
+      }
+      rovers.push(rover);
+    }
+    let paths = self.rovers.iter().map(|mission_rover| mission_rover.path.trim().to_string()).collect();
+    Ok((PlateauMap { plateau, rovers }, paths))
+  }
+}
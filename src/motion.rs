@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 use crate::Error;
@@ -12,14 +14,29 @@ pub enum Motion {
 impl Motion {
   pub fn parse_path(s: &str) -> Result<Vec<Self>, Error> {
     let mut path = Vec::new();
-    for maybe_motion in s.chars() {
+    for (n, maybe_motion) in s.chars().enumerate() {
       match maybe_motion {
-        'L' => path.push(Self::TurnLeft),
-        'R' => path.push(Self::TurnRight),
-        'M' => path.push(Self::MoveStraight),
-        m => return Err(Error::MotionParseError(format!("Invalid motion '{}'", m))),
+        'L' | 'l' => path.push(Self::TurnLeft),
+        'R' | 'r' => path.push(Self::TurnRight),
+        'M' | 'm' => path.push(Self::MoveStraight),
+        m => return Err(Error::MotionParseError(format!("Invalid motion '{}' at position {}", m, n + 1))),
       }
     }
     Ok(path)
   }
+
+  /// Writes the motions back as a path, the way [`Motion::parse_path`] reads them.
+  pub fn to_path(motion_vector: &[Self]) -> String {
+    motion_vector.iter().map(|motion| motion.to_string()).collect()
+  }
+}
+
+impl fmt::Display for Motion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::TurnLeft => write!(f, "L"),
+      Self::TurnRight => write!(f, "R"),
+      Self::MoveStraight => write!(f, "M"),
+    }
+  }
 }
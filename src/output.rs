@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::db::DatabaseInfo;
+use crate::{Error, Plateau, Rover};
+
+/// How the CLI prints plateaus and rovers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+  /// The `Display` text, meant for people.
+  #[default]
+  Text,
+  /// A single JSON array.
+  Json,
+  /// One JSON object per line.
+  Ndjson,
+  /// A header line followed by one row per record.
+  Csv,
+}
+
+impl FromStr for OutputFormat {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      "ndjson" => Ok(Self::Ndjson),
+      "csv" => Ok(Self::Csv),
+      other => Err(Error::FormatParseError(format!("Expected text, json, ndjson or csv but found '{}'", other))),
+    }
+  }
+}
+
+/// A record with a fixed set of columns, for CSV.
+pub trait CsvRecord {
+  const HEADER: &'static [&'static str];
+
+  fn csv_fields(&self) -> Vec<String>;
+}
+
+impl CsvRecord for Plateau {
+  const HEADER: &'static [&'static str] = &["id", "name", "x_max", "y_max", "tags"];
+
+  fn csv_fields(&self) -> Vec<String> {
+    vec![
+      self.id().to_string(),
+      self.name().unwrap_or_default().to_string(),
+      self.x_max().to_string(),
+      self.y_max().to_string(),
+      join_tags(self.tags().iter()),
+    ]
+  }
+}
+
+impl CsvRecord for Rover {
+  const HEADER: &'static [&'static str] = &["id", "name", "x", "y", "facing", "tags", "battery", "max_slope"];
+
+  fn csv_fields(&self) -> Vec<String> {
+    vec![
+      self.id().to_string(),
+      self.name().unwrap_or_default().to_string(),
+      self.x().to_string(),
+      self.y().to_string(),
+      self.facing().to_string(),
+      join_tags(self.tags().iter()),
+      self.battery().map(|battery| battery.to_string()).unwrap_or_default(),
+      self.max_slope().map(|max_slope| max_slope.to_string()).unwrap_or_default(),
+    ]
+  }
+}
+
+impl CsvRecord for DatabaseInfo {
+  const HEADER: &'static [&'static str] = &["plateaus", "rovers", "history", "schema_version", "pending_migrations"];
+
+  fn csv_fields(&self) -> Vec<String> {
+    vec![
+      self.plateaus.to_string(),
+      self.rovers.to_string(),
+      self.history.to_string(),
+      self.schema_version.clone().unwrap_or_default(),
+      self.pending_migrations.to_string(),
+    ]
+  }
+}
+
+/// A rover once it has moved, with the traversal cost of its moves.
+#[derive(Debug, Serialize)]
+pub struct MovedRover<'a> {
+  #[serde(flatten)]
+  pub rover: &'a Rover,
+  pub cost: i32,
+}
+
+impl CsvRecord for MovedRover<'_> {
+  const HEADER: &'static [&'static str] = &["id", "name", "x", "y", "facing", "tags", "battery", "max_slope", "cost"];
+
+  fn csv_fields(&self) -> Vec<String> {
+    let mut fields = self.rover.csv_fields();
+    fields.push(self.cost.to_string());
+    fields
+  }
+}
+
+/// A moved rover with the id or name of its plateau, for the output of several plateaus at once.
+#[derive(Debug, Serialize)]
+pub struct PlateauRover<'a> {
+  pub plateau: &'a str,
+  #[serde(flatten)]
+  pub rover: MovedRover<'a>,
+}
+
+impl CsvRecord for PlateauRover<'_> {
+  const HEADER: &'static [&'static str] = &["plateau", "id", "name", "x", "y", "facing", "tags", "battery", "max_slope", "cost"];
+
+  fn csv_fields(&self) -> Vec<String> {
+    let mut fields = vec![self.plateau.to_string()];
+    fields.extend(self.rover.csv_fields());
+    fields
+  }
+}
+
+/// The tags of a record in a single field, separated by `;`.
+fn join_tags<'a>(tags: impl Iterator<Item = &'a String>) -> String {
+  tags.map(String::as_str).collect::<Vec<&str>>().join(";")
+}
+
+/// Quotes a CSV field holding a separator, a quote or a line break, doubling its quotes.
+fn csv_field(field: &str) -> String {
+  match field.contains([',', '"', '\n', '\r']) {
+    true => format!("\"{}\"", field.replace('"', "\"\"")),
+    false => field.to_string(),
+  }
+}
+
+/// Writes records in a machine-readable format, every line ending with a line break.
+/// The text format is left to the `Display` implementations, so it writes nothing.
+pub fn records<T: Serialize + CsvRecord>(format: OutputFormat, records: &[T]) -> Result<String, serde_json::Error> {
+  let mut text = String::new();
+  match format {
+    OutputFormat::Text => (),
+    OutputFormat::Json => {
+      text.push_str(&serde_json::to_string_pretty(records)?);
+      text.push('\n');
+    }
+    OutputFormat::Ndjson => {
+      for record in records {
+        text.push_str(&serde_json::to_string(record)?);
+        text.push('\n');
+      }
+    }
+    OutputFormat::Csv => {
+      text.push_str(&T::HEADER.join(","));
+      text.push('\n');
+      for record in records {
+        text.push_str(&record.csv_fields().iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(","));
+        text.push('\n');
+      }
+    }
+  }
+  Ok(text)
+}
@@ -1,29 +1,49 @@
+use std::collections::{BTreeSet, HashSet};
 use std::{fmt, ops::Deref, str::FromStr};
 
 use actix_web::{
-  web::{self, Data, Json, Path},
+  dev::RequestHead,
+  error::BlockingError,
+  http::header::CONTENT_TYPE,
+  web::{self, Data, Json, Path, Query},
   HttpResponse,
 };
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
-use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl};
+use diesel::{Connection, ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
+use crate::geo::{ExportFormat, Georeference};
+use crate::heightmap::Heightmap;
+use crate::history;
+use crate::labels::{self, Tags};
+use crate::map::PlateauMap;
+use crate::render::{self, Trajectory};
+use crate::simulation::{BlockedPolicy, Simulation, SimulationReport};
+use crate::store::Store;
+use crate::terrain::{Terrain, TerrainMap};
+use crate::DBPooledConnection;
+use crate::APPLICATION_JSON;
 use crate::{rover::Rovers, Error};
 use crate::{
-  rover::{RoverDB, RoverRequest},
+  rover::{Blocked, MoveReport, RoverDB, RoverRequest, Stop},
   schema::plateaus,
-  Motion, Rover,
+  Motion, Pose, Rover,
 };
-use crate::{DBPool, DBPooledConnection};
-use crate::{APPLICATION_JSON, CONNECTION_POOL_ERROR};
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct Plateaus(Vec<Plateau>);
+pub struct Plateaus(pub(crate) Vec<Plateau>);
 
 impl Plateaus {
   pub fn new() -> Self {
-    Self { 0: vec![] }
+    Self(vec![])
+  }
+}
+
+impl Default for Plateaus {
+  fn default() -> Self {
+    Self::new()
   }
 }
 
@@ -35,12 +55,32 @@ impl Deref for Plateaus {
   }
 }
 
+/// A set of cells of a plateau, as `(x, y)` co-ordinates.
+pub type Cells = BTreeSet<(i32, i32)>;
+
+/// The largest `x_max` or `y_max` of a plateau, which keeps its maps, terrain and drawings to a manageable size.
+pub const MAX_BOUND: i32 = 1000;
+
 #[derive(Debug, Clone, Deserialize, Serialize, AsExpression)]
 pub struct Plateau {
   id: String,
   created_at: DateTime<Utc>,
   x_max: i32,
   y_max: i32,
+  #[serde(default)]
+  name: Option<String>,
+  #[serde(default)]
+  tags: Tags,
+  #[serde(default)]
+  metadata: Value,
+  #[serde(default)]
+  charging_stations: Cells,
+  #[serde(default)]
+  terrain: TerrainMap,
+  #[serde(default)]
+  heightmap: Option<Heightmap>,
+  #[serde(default)]
+  georeference: Option<Georeference>,
 }
 
 impl Plateau {
@@ -50,6 +90,24 @@ impl Plateau {
       created_at: Utc::now(),
       x_max,
       y_max,
+      name: None,
+      tags: Tags::new(),
+      metadata: Value::Null,
+      charging_stations: Cells::new(),
+      terrain: TerrainMap::new(),
+      heightmap: None,
+      georeference: None,
+    }
+  }
+
+  /// Checks the bounds of a new plateau, which must be between 0 and [`MAX_BOUND`].
+  pub fn check_bounds(x_max: i32, y_max: i32) -> Result<(), Error> {
+    match (0..=MAX_BOUND).contains(&x_max) && (0..=MAX_BOUND).contains(&y_max) {
+      true => Ok(()),
+      false => Err(Error::CoOrdinateParseError(format!(
+        "Expected plateau bounds between 0 and {} but found {} {}",
+        MAX_BOUND, x_max, y_max
+      ))),
     }
   }
 
@@ -57,6 +115,31 @@ impl Plateau {
     &self.id
   }
 
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// The name of the plateau if it has one, its id otherwise.
+  pub fn label(&self) -> &str {
+    self.name().unwrap_or(&self.id)
+  }
+
+  pub fn set_name(&mut self, name: Option<String>) -> Result<(), Error> {
+    if let Some(name) = &name {
+      labels::validate_name(name)?;
+    }
+    self.name = name;
+    Ok(())
+  }
+
+  pub fn tags(&self) -> &Tags {
+    &self.tags
+  }
+
+  pub fn metadata(&self) -> &Value {
+    &self.metadata
+  }
+
   pub fn x_max(&self) -> i32 {
     self.x_max
   }
@@ -65,16 +148,145 @@ impl Plateau {
     self.y_max
   }
 
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    (0..=self.x_max).contains(&x) && (0..=self.y_max).contains(&y)
+  }
+
+  /// The cells that recharge the battery of a rover on them.
+  pub fn charging_stations(&self) -> &Cells {
+    &self.charging_stations
+  }
+
+  pub fn set_charging_stations(&mut self, charging_stations: Cells) -> Result<(), Error> {
+    self.check_cells("Charging station", &charging_stations)?;
+    self.charging_stations = charging_stations;
+    Ok(())
+  }
+
+  pub fn is_charging_station(&self, x: i32, y: i32) -> bool {
+    self.charging_stations.contains(&(x, y))
+  }
+
+  pub fn terrain(&self) -> &TerrainMap {
+    &self.terrain
+  }
+
+  pub fn set_terrain(&mut self, terrain: TerrainMap) -> Result<(), Error> {
+    self.check_cells("Terrain cell", &terrain.cells())?;
+    self.terrain = terrain;
+    Ok(())
+  }
+
+  /// The terrain of a cell, rock unless the terrain layer says otherwise.
+  pub fn terrain_at(&self, x: i32, y: i32) -> Terrain {
+    self.terrain.get(x, y)
+  }
+
+  pub fn is_passable(&self, x: i32, y: i32) -> bool {
+    self.terrain_at(x, y).cost().is_some()
+  }
+
+  /// Without a heightmap the plateau is flat.
+  pub fn heightmap(&self) -> Option<&Heightmap> {
+    self.heightmap.as_ref()
+  }
+
+  /// Sets the heightmap, which must hold a height for every cell of the plateau.
+  pub fn set_heightmap(&mut self, heightmap: Option<Heightmap>) -> Result<(), Error> {
+    if let Some(heightmap) = &heightmap {
+      heightmap.check()?;
+      let (width, height) = match (usize::try_from(self.x_max), usize::try_from(self.y_max)) {
+        (Ok(x_max), Ok(y_max)) => (x_max + 1, y_max + 1),
+        _ => {
+          return Err(Error::HeightmapParseError(format!(
+            "The plateau bounds {} {} can't be negative",
+            self.x_max, self.y_max
+          )))
+        }
+      };
+      if (heightmap.width(), heightmap.height()) != (width, height) {
+        return Err(Error::HeightmapParseError(format!(
+          "Expected {}x{} heights but found {}x{}",
+          width,
+          height,
+          heightmap.width(),
+          heightmap.height()
+        )));
+      }
+    }
+    self.heightmap = heightmap;
+    Ok(())
+  }
+
+  pub fn height_at(&self, x: i32, y: i32) -> i32 {
+    self.heightmap.as_ref().and_then(|heightmap| heightmap.get(x, y)).unwrap_or_default()
+  }
+
+  /// The height difference between two cells.
+  pub fn slope(&self, from: (i32, i32), to: (i32, i32)) -> i32 {
+    (self.height_at(to.0, to.1) - self.height_at(from.0, from.1)).abs()
+  }
+
+  /// Without a georeference the plateau can't be exported to GIS formats.
+  pub fn georeference(&self) -> Option<&Georeference> {
+    self.georeference.as_ref()
+  }
+
+  pub fn set_georeference(&mut self, georeference: Option<Georeference>) -> Result<(), Error> {
+    if let Some(georeference) = &georeference {
+      georeference.check()?;
+    }
+    self.georeference = georeference;
+    Ok(())
+  }
+
+  fn check_cells(&self, kind: &str, cells: &Cells) -> Result<(), Error> {
+    match cells.iter().find(|(x, y)| !self.contains(*x, *y)) {
+      Some((x, y)) => Err(Error::CoOrdinateParseError(format!("{} {} {} is outside the plateau", kind, x, y))),
+      None => Ok(()),
+    }
+  }
+
+  /// The request creating a plateau like this one, as the REST API takes it. The id is left to the server.
+  pub fn to_request(&self) -> PlateauRequest {
+    PlateauRequest {
+      x_max: self.x_max,
+      y_max: self.y_max,
+      name: self.name.clone(),
+      tags: self.tags.clone(),
+      metadata: self.metadata.clone(),
+      charging_stations: self.charging_stations.clone(),
+      terrain: self.terrain.clone(),
+      heightmap: self.heightmap.clone(),
+      georeference: self.georeference.clone(),
+    }
+  }
+
   fn to_plateau_db(&self) -> PlateauDB {
     PlateauDB {
       id: self.id.clone(),
-      created_at: Utc::now().naive_utc(),
+      created_at: self.created_at.naive_utc(),
       x_max: self.x_max,
       y_max: self.y_max,
+      name: self.name.clone(),
+      tags: labels::tags_to_db(&self.tags),
+      metadata: labels::metadata_to_db(&self.metadata),
+      charging_stations: cells_to_db(&self.charging_stations),
+      terrain: serde_json::to_string(&self.terrain).unwrap_or_else(|_| "{}".into()),
+      heightmap: self.heightmap.as_ref().and_then(|heightmap| serde_json::to_string(heightmap).ok()),
+      georeference: self.georeference.as_ref().and_then(|georeference| serde_json::to_string(georeference).ok()),
     }
   }
 }
 
+fn cells_to_db(cells: &Cells) -> String {
+  serde_json::to_string(cells).unwrap_or_else(|_| "[]".into())
+}
+
+fn cells_from_db(cells: &str) -> Cells {
+  serde_json::from_str(cells).unwrap_or_default()
+}
+
 #[derive(Queryable, Insertable, AsChangeset)]
 #[table_name = "plateaus"]
 struct PlateauDB {
@@ -82,6 +294,13 @@ struct PlateauDB {
   created_at: NaiveDateTime,
   x_max: i32,
   y_max: i32,
+  name: Option<String>,
+  tags: String,
+  metadata: Option<String>,
+  charging_stations: String,
+  terrain: String,
+  heightmap: Option<String>,
+  georeference: Option<String>,
 }
 
 impl PlateauDB {
@@ -91,6 +310,13 @@ impl PlateauDB {
       created_at: Utc.from_utc_datetime(&self.created_at),
       x_max: self.x_max,
       y_max: self.y_max,
+      name: self.name.clone(),
+      tags: labels::tags_from_db(&self.tags),
+      metadata: labels::metadata_from_db(&self.metadata),
+      charging_stations: cells_from_db(&self.charging_stations),
+      terrain: serde_json::from_str(&self.terrain).unwrap_or_default(),
+      heightmap: self.heightmap.as_deref().and_then(|heightmap| serde_json::from_str(heightmap).ok()),
+      georeference: self.georeference.as_deref().and_then(|georeference| serde_json::from_str(georeference).ok()),
     }
   }
 }
@@ -99,14 +325,56 @@ impl PlateauDB {
 pub struct PlateauRequest {
   pub x_max: i32,
   pub y_max: i32,
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub tags: Tags,
+  #[serde(default)]
+  pub metadata: Value,
+  #[serde(default)]
+  pub charging_stations: Cells,
+  #[serde(default)]
+  pub terrain: TerrainMap,
+  #[serde(default)]
+  pub heightmap: Option<Heightmap>,
+  #[serde(default)]
+  pub georeference: Option<Georeference>,
 }
 
 impl PlateauRequest {
-  fn to_plateau(&self) -> Plateau {
-    Plateau::new(self.x_max, self.y_max)
+  pub fn to_plateau(&self) -> Result<Plateau, Error> {
+    Plateau::check_bounds(self.x_max, self.y_max)?;
+    if let Some(name) = &self.name {
+      labels::validate_name(name)?;
+    }
+    let mut plateau = Plateau {
+      name: self.name.clone(),
+      tags: self.tags.clone(),
+      metadata: self.metadata.clone(),
+      ..Plateau::new(self.x_max, self.y_max)
+    };
+    plateau.set_charging_stations(self.charging_stations.clone())?;
+    plateau.set_terrain(self.terrain.clone())?;
+    plateau.set_heightmap(self.heightmap.clone())?;
+    plateau.set_georeference(self.georeference.clone())?;
+    Ok(plateau)
   }
 }
 
+fn error_response(error: BlockingError<Error>) -> HttpResponse {
+  let error = match error {
+    BlockingError::Error(error) => error,
+    BlockingError::Canceled => return HttpResponse::InternalServerError().content_type(APPLICATION_JSON).finish(),
+  };
+  let mut response = match &error {
+    Error::DatabaseError(diesel::result::Error::NotFound) | Error::RoverNotFound(_) | Error::PlateauNotFound(_) => HttpResponse::NotFound(),
+    Error::CollisionError(_) | Error::NameTaken(_) => HttpResponse::Conflict(),
+    Error::DatabaseError(_) => HttpResponse::InternalServerError(),
+    _ => HttpResponse::BadRequest(),
+  };
+  response.content_type(APPLICATION_JSON).json(json!({ "error": error.to_string() }))
+}
+
 pub fn get_plateau(_plateau_id: Uuid, conn: &DBPooledConnection) -> Result<Plateau, diesel::result::Error> {
   use crate::schema::plateaus::dsl::*;
 
@@ -120,35 +388,61 @@ pub fn get_plateau(_plateau_id: Uuid, conn: &DBPooledConnection) -> Result<Plate
   }
 }
 
+pub fn get_plateau_by_name(_name: &str, conn: &DBPooledConnection) -> Result<Plateau, diesel::result::Error> {
+  use crate::schema::plateaus::dsl::*;
+
+  let res = plateaus.filter(name.eq(_name)).load::<PlateauDB>(conn);
+  match res {
+    Ok(plateaus_db) => match plateaus_db.first() {
+      Some(plateau_db) => Ok(plateau_db.to_plateau()),
+      _ => Err(diesel::result::Error::NotFound),
+    },
+    Err(err) => Err(err),
+  }
+}
+
+/// Looks up a plateau by either its id or its name.
+pub fn find_plateau(key: &str, conn: &DBPooledConnection) -> Result<Plateau, diesel::result::Error> {
+  match Uuid::from_str(key) {
+    Ok(_plateau_id) => get_plateau(_plateau_id, conn),
+    Err(_) => get_plateau_by_name(key, conn),
+  }
+}
+
 #[get("/plateaus/{plateau_id}")]
-pub async fn async_get(path: Path<(String,)>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-  let (id,) = path.0;
-  let plateau = web::block(move || get_plateau(Uuid::from_str(&id).unwrap(), &conn)).await;
+pub async fn async_get(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (key,) = path.0;
+  let plateau = web::block(move || store.find_plateau(&key)).await;
 
   match plateau {
     Ok(plateau) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(plateau),
-    _ => HttpResponse::NoContent().content_type(APPLICATION_JSON).await.unwrap(),
+    Err(error) => error_response(error),
   }
 }
 
 pub fn list_plateaus(total_plateaus: i64, conn: &DBPooledConnection) -> Result<Plateaus, diesel::result::Error> {
   use crate::schema::plateaus::dsl::*;
 
-  let _plateaus = match plateaus.order(created_at.desc()).limit(total_plateaus).load::<PlateauDB>(conn) {
-    Ok(_plateaus) => _plateaus,
-    Err(_) => vec![],
-  };
+  let _plateaus = plateaus
+    .order(created_at.desc())
+    .limit(total_plateaus)
+    .load::<PlateauDB>(conn)
+    .unwrap_or_default();
 
-  Ok(Plateaus {
-    0: _plateaus.into_iter().map(|r| r.to_plateau()).collect::<Vec<Plateau>>(),
-  })
+  Ok(Plateaus(_plateaus.into_iter().map(|r| r.to_plateau()).collect::<Vec<Plateau>>()))
+}
+
+/// How many plateaus or rovers a listing holds at most, 50 by default.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListOptions {
+  #[serde(default)]
+  pub limit: Option<i64>,
 }
 
 #[get("/plateaus")]
-pub async fn async_list(pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-  let plateaus = web::block(move || list_plateaus(50, &conn)).await.unwrap();
+pub async fn async_list(options: Query<ListOptions>, store: Data<dyn Store>) -> HttpResponse {
+  let limit = options.limit.unwrap_or(50);
+  let plateaus = web::block(move || store.list_plateaus(limit)).await.unwrap();
 
   HttpResponse::Ok().content_type(APPLICATION_JSON).json(plateaus)
 }
@@ -157,20 +451,315 @@ pub fn create_plateau(plateau: Plateau, conn: &DBPooledConnection) -> Result<Pla
   use crate::schema::plateaus::dsl::*;
 
   let plateau_db = plateau.to_plateau_db();
-  let _ = diesel::insert_into(plateaus).values(&plateau_db).execute(conn);
+  diesel::insert_into(plateaus).values(&plateau_db).execute(conn)?;
 
   Ok(plateau_db.to_plateau())
 }
 
 #[post("/plateaus")]
-pub async fn async_create(plateau_request: Json<PlateauRequest>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-
-  let plateau = web::block(move || create_plateau(plateau_request.to_plateau(), &conn)).await;
+pub async fn async_create(plateau_request: Json<PlateauRequest>, store: Data<dyn Store>) -> HttpResponse {
+  let plateau = match plateau_request.to_plateau() {
+    Ok(plateau) => plateau,
+    Err(error) => return error_response(BlockingError::Error(error)),
+  };
+  let plateau = web::block(move || store.create_plateau(plateau)).await;
 
   match plateau {
     Ok(plateau) => HttpResponse::Created().content_type(APPLICATION_JSON).json(plateau),
-    _ => HttpResponse::NoContent().await.unwrap(),
+    Err(error) => error_response(error),
+  }
+}
+
+/// Creates the plateau of a map along with its rovers, in a single transaction.
+pub fn create_map(map: PlateauMap, conn: &DBPooledConnection) -> Result<PlateauMap, diesel::result::Error> {
+  conn.transaction(|| {
+    let plateau = create_plateau(map.plateau, conn)?;
+    let _plateau_id = Uuid::from_str(plateau.id()).unwrap();
+    let mut _rovers = Vec::new();
+    for rover in map.rovers {
+      _rovers.push(create_rover(_plateau_id, rover, conn)?);
+    }
+    Ok(PlateauMap { plateau, rovers: _rovers })
+  })
+}
+
+/// Puts back a plateau with its rovers and their history as they were, replacing a plateau with the same id, in a
+/// single transaction.
+pub fn import_plateau(
+  plateau: Plateau,
+  rovers: Vec<Rover>,
+  entries: Vec<history::HistoryEntry>,
+  conn: &DBPooledConnection,
+) -> Result<(), diesel::result::Error> {
+  let _plateau_id = plateau.id().to_string();
+  conn.transaction(|| {
+    diesel::delete(crate::schema::history::table.filter(crate::schema::history::plateau_id.eq(&_plateau_id))).execute(conn)?;
+    diesel::delete(crate::schema::rovers::table.filter(crate::schema::rovers::plateau_id.eq(&_plateau_id))).execute(conn)?;
+    diesel::delete(crate::schema::plateaus::table.find(&_plateau_id)).execute(conn)?;
+    diesel::insert_into(crate::schema::plateaus::table)
+      .values(&plateau.to_plateau_db())
+      .execute(conn)?;
+    for rover in &rovers {
+      diesel::insert_into(crate::schema::rovers::table)
+        .values(&rover.to_rover_db(_plateau_id.clone()))
+        .execute(conn)?;
+    }
+    history::import(&_plateau_id, &entries, conn)
+  })
+}
+
+/// Deletes a plateau along with its rovers and their history, in a single transaction, giving back the number of
+/// rovers deleted.
+pub fn delete_plateau(_plateau_id: Uuid, conn: &DBPooledConnection) -> Result<usize, diesel::result::Error> {
+  conn.transaction(|| {
+    diesel::delete(crate::schema::history::table.filter(crate::schema::history::plateau_id.eq(_plateau_id.to_string()))).execute(conn)?;
+    let _rovers = diesel::delete(crate::schema::rovers::table.filter(crate::schema::rovers::plateau_id.eq(_plateau_id.to_string()))).execute(conn)?;
+    match diesel::delete(crate::schema::plateaus::table.find(_plateau_id.to_string())).execute(conn)? {
+      0 => Err(diesel::result::Error::NotFound),
+      _ => Ok(_rovers),
+    }
+  })
+}
+
+#[delete("/plateaus/{plateau_id}")]
+pub async fn async_delete(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let rovers = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.delete_plateau(Uuid::from_str(plateau.id()).unwrap())
+  })
+  .await;
+
+  match rovers {
+    Ok(rovers) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(json!({ "rovers": rovers })),
+    Err(error) => error_response(error),
+  }
+}
+
+fn is_text_plain(head: &RequestHead) -> bool {
+  head
+    .headers()
+    .get(CONTENT_TYPE)
+    .and_then(|content_type| content_type.to_str().ok())
+    .is_some_and(|content_type| content_type.starts_with("text/plain"))
+}
+
+/// Creates a plateau with its obstacles and rovers from a map, see [`PlateauMap`].
+#[post("/plateaus", guard = "is_text_plain")]
+pub async fn async_create_map(body: String, store: Data<dyn Store>) -> HttpResponse {
+  let map = web::block(move || {
+    let map = PlateauMap::from_str(&body)?;
+    store.create_map(map)
+  })
+  .await;
+
+  match map {
+    Ok(map) => HttpResponse::Created().content_type(APPLICATION_JSON).json(map),
+    Err(error) => error_response(error),
+  }
+}
+
+/// Replaces the terrain layer of a plateau.
+pub fn set_plateau_terrain(_plateau_id: Uuid, _terrain: TerrainMap, conn: &DBPooledConnection) -> Result<Plateau, Error> {
+  use crate::schema::plateaus::dsl::*;
+
+  let mut plateau = get_plateau(_plateau_id, conn)?;
+  plateau.set_terrain(_terrain)?;
+  diesel::update(plateaus.find(plateau.id()))
+    .set(terrain.eq(serde_json::to_string(plateau.terrain()).unwrap_or_else(|_| "{}".into())))
+    .execute(conn)?;
+
+  Ok(plateau)
+}
+
+#[put("/plateaus/{plateau_id}/terrain")]
+pub async fn async_set_terrain(path: Path<(String,)>, terrain_request: Json<TerrainMap>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let plateau = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.set_plateau_terrain(Uuid::from_str(plateau.id()).unwrap(), terrain_request.into_inner())
+  })
+  .await;
+
+  match plateau {
+    Ok(plateau) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(plateau),
+    Err(error) => error_response(error),
+  }
+}
+
+/// Replaces the heightmap of a plateau.
+pub fn set_plateau_heightmap(_plateau_id: Uuid, _heightmap: Option<Heightmap>, conn: &DBPooledConnection) -> Result<Plateau, Error> {
+  use crate::schema::plateaus::dsl::*;
+
+  let mut plateau = get_plateau(_plateau_id, conn)?;
+  plateau.set_heightmap(_heightmap)?;
+  diesel::update(plateaus.find(plateau.id()))
+    .set(heightmap.eq(plateau.heightmap().and_then(|_heightmap| serde_json::to_string(_heightmap).ok())))
+    .execute(conn)?;
+
+  Ok(plateau)
+}
+
+/// Takes the heightmap as a PGM image or a CSV grid.
+#[put("/plateaus/{plateau_id}/heightmap")]
+pub async fn async_set_heightmap(path: Path<(String,)>, body: web::Bytes, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let plateau = web::block(move || {
+    let _heightmap = Heightmap::parse(&body)?;
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.set_plateau_heightmap(Uuid::from_str(plateau.id()).unwrap(), Some(_heightmap))
+  })
+  .await;
+
+  match plateau {
+    Ok(plateau) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(plateau),
+    Err(error) => error_response(error),
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenderOptions {
+  /// Comma separated `<rover>:<path>` pairs, e.g. `spirit:LMLM,opportunity:MMR`.
+  #[serde(default)]
+  pub paths: Option<String>,
+}
+
+#[get("/plateaus/{plateau_id}/map.svg")]
+pub async fn async_render_svg(path: Path<(String,)>, options: Query<RenderOptions>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let svg = web::block(move || {
+    let mut paths = Vec::new();
+    for pair in options.paths.iter().flat_map(|paths| paths.split(',')).filter(|pair| !pair.is_empty()) {
+      match pair.rsplit_once(':') {
+        Some((rover_key, motion_path)) => paths.push((rover_key.to_string(), Motion::parse_path(motion_path)?)),
+        None => return Err(Error::MotionParseError(format!("Expected <rover>:<path> but found '{}'", pair))),
+      }
+    }
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.render_plateau(Uuid::from_str(plateau.id()).unwrap(), paths)
+  })
+  .await;
+
+  match svg {
+    Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+    Err(error) => error_response(error),
+  }
+}
+
+/// Replaces the georeference of a plateau.
+pub fn set_plateau_georeference(_plateau_id: Uuid, _georeference: Option<Georeference>, conn: &DBPooledConnection) -> Result<Plateau, Error> {
+  use crate::schema::plateaus::dsl::*;
+
+  let mut plateau = get_plateau(_plateau_id, conn)?;
+  plateau.set_georeference(_georeference)?;
+  diesel::update(plateaus.find(plateau.id()))
+    .set(georeference.eq(plateau.georeference().and_then(|_georeference| serde_json::to_string(_georeference).ok())))
+    .execute(conn)?;
+
+  Ok(plateau)
+}
+
+#[put("/plateaus/{plateau_id}/georeference")]
+pub async fn async_set_georeference(path: Path<(String,)>, georeference_request: Json<Georeference>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let plateau = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.set_plateau_georeference(Uuid::from_str(plateau.id()).unwrap(), Some(georeference_request.into_inner()))
+  })
+  .await;
+
+  match plateau {
+    Ok(plateau) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(plateau),
+    Err(error) => error_response(error),
+  }
+}
+
+#[get("/plateaus/{plateau_id}/map.geojson")]
+pub async fn async_export_geojson(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let geojson = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.export_plateau(Uuid::from_str(plateau.id()).unwrap(), ExportFormat::GeoJson)
+  })
+  .await;
+
+  match geojson {
+    Ok(geojson) => HttpResponse::Ok().content_type("application/geo+json").body(geojson),
+    Err(error) => error_response(error),
+  }
+}
+
+#[get("/plateaus/{plateau_id}/map.kml")]
+pub async fn async_export_kml(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let kml = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.export_plateau(Uuid::from_str(plateau.id()).unwrap(), ExportFormat::Kml)
+  })
+  .await;
+
+  match kml {
+    Ok(kml) => HttpResponse::Ok().content_type("application/vnd.google-earth.kml+xml").body(kml),
+    Err(error) => error_response(error),
+  }
+}
+
+#[get("/plateaus/{plateau_id}/history")]
+pub async fn async_get_history(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let entries = web::block(move || {
+    store
+      .find_plateau(&plateau_key)
+      .and_then(|plateau| store.list_history(Uuid::from_str(plateau.id()).unwrap()))
+  })
+  .await;
+
+  match entries {
+    Ok(entries) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(entries),
+    Err(error) => error_response(error),
+  }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayOptions {
+  /// How long every frame of an animation is shown, in seconds.
+  #[serde(default = "default_frame_duration")]
+  pub frame_duration: f64,
+}
+
+fn default_frame_duration() -> f64 {
+  0.5
+}
+
+#[get("/plateaus/{plateau_id}/replay.svg")]
+pub async fn async_replay_svg(path: Path<(String,)>, options: Query<ReplayOptions>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let svg = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    let (plateau, frames) = store.replay_plateau(Uuid::from_str(plateau.id()).unwrap())?;
+    render::animated_svg(&plateau, &frames, options.frame_duration)
+  })
+  .await;
+
+  match svg {
+    Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+    Err(error) => error_response(error),
+  }
+}
+
+#[get("/plateaus/{plateau_id}/replay.txt")]
+pub async fn async_replay_text(path: Path<(String,)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let text = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    let (plateau, frames) = store.replay_plateau(Uuid::from_str(plateau.id()).unwrap())?;
+    Ok::<String, Error>(render::ascii_frames(&plateau, &frames))
+  })
+  .await;
+
+  match text {
+    Ok(text) => HttpResponse::Ok().content_type("text/plain").body(text),
+    Err(error) => error_response(error),
   }
 }
 
@@ -178,7 +767,7 @@ impl FromStr for Plateau {
   type Err = Error;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
-    let co_ordinates: Vec<&str> = s.trim().split(' ').collect();
+    let co_ordinates: Vec<&str> = s.split_whitespace().collect();
 
     match co_ordinates.len() {
       len if len < 2 => Err(Error::CoOrdinateParseError("Y co-ordinate may be missing".into())),
@@ -186,13 +775,9 @@ impl FromStr for Plateau {
       _ => {
         let x_max: i32 = co_ordinates[0].parse()?;
         let y_max: i32 = co_ordinates[1].parse()?;
+        Self::check_bounds(x_max, y_max)?;
 
-        Ok(Self {
-          id: Uuid::new_v4().to_hyphenated().to_string(),
-          created_at: Utc::now(),
-          x_max,
-          y_max,
-        })
+        Ok(Self::new(x_max, y_max))
       }
     }
   }
@@ -208,48 +793,392 @@ impl fmt::Display for Plateaus {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     writeln!(f, "{{")?;
     for plateau in self.iter() {
-      writeln!(f, "  {}: {} {}", plateau.id, plateau.x_max, plateau.y_max)?;
+      match &plateau.name {
+        Some(name) => writeln!(f, "  {} ({}): {} {}", plateau.id, name, plateau.x_max, plateau.y_max)?,
+        None => writeln!(f, "  {}: {} {}", plateau.id, plateau.x_max, plateau.y_max)?,
+      }
     }
     write!(f, "}}")
   }
 }
 
-pub fn move_rover(_plateau_id: Uuid, _rover_id: Uuid, motion_vector: Vec<Motion>, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
-  let rover = get_rover(_plateau_id, _rover_id, conn);
-  let plateau = get_plateau(_plateau_id, conn);
-  match plateau {
-    Ok(plateau) => match rover {
-      Ok(mut rover) => {
-        rover.apply_motion_vector(motion_vector, &plateau);
-        use crate::schema::rovers::dsl::*;
-        let _ = diesel::update(rovers.find(_rover_id.to_string()))
-          .set(rover.to_rover_db(_plateau_id.to_string()))
-          .execute(conn);
-        Ok(rover)
+pub fn move_rover(_plateau_id: Uuid, _rover_id: Uuid, motion_vector: Vec<Motion>, conn: &DBPooledConnection) -> Result<MoveReport, diesel::result::Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let plateau = get_plateau(_plateau_id, conn)?;
+  let mut rover = get_rover(_plateau_id, _rover_id, conn)?;
+  let (start, report) = move_single(&plateau, &mut rover, &motion_vector);
+  conn.transaction(|| {
+    diesel::update(rovers.find(_rover_id.to_string()))
+      .set(report.rover.to_rover_db(_plateau_id.to_string()))
+      .execute(conn)?;
+    history::record(
+      plateau.id(),
+      report.rover.id(),
+      &history::new_batch(),
+      &Motion::to_path(&motion_vector),
+      &start,
+      &report.trip.poses,
+      conn,
+    )
+  })?;
+  Ok(report)
+}
+
+/// Moves a rover along a motion path, giving back the pose it started from along with its report.
+pub(crate) fn move_single(plateau: &Plateau, rover: &mut Rover, motion_vector: &[Motion]) -> (Pose, MoveReport) {
+  let start = rover.pose();
+  let trip = rover.apply_motion_vector(motion_vector.to_vec(), plateau);
+  (start, MoveReport { rover: rover.clone(), trip })
+}
+
+/// A moved rover, along with the steps blocked by impassable terrain, the step its battery ran out on if it did
+/// and the traversal cost of its moves.
+#[derive(Serialize)]
+struct MovedRover {
+  #[serde(flatten)]
+  rover: Rover,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  blocked: Vec<Blocked>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  depleted: Option<usize>,
+  cost: i32,
+  /// The cells the rover went through, the starting cell first.
+  trajectory: Trajectory,
+}
+
+#[patch("/plateaus/{plateau_id}/rovers/{rover_id}/{motion}")]
+pub async fn async_move_rover(path: Path<(String, String, String)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key, rover_key, motion_path) = path.0;
+  let motion_vector = Motion::parse_path(&motion_path);
+  match motion_vector {
+    Ok(motion_vector) => {
+      let report = web::block(move || {
+        let plateau = store.find_plateau(&plateau_key)?;
+        let _plateau_id = Uuid::from_str(plateau.id()).unwrap();
+        let rover = store.find_rover(_plateau_id, &rover_key)?;
+        store.move_rover(_plateau_id, Uuid::from_str(rover.id()).unwrap(), motion_vector)
+      })
+      .await;
+      match report {
+        Ok(report) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(MovedRover {
+          rover: report.rover,
+          blocked: report.trip.blocked,
+          depleted: report.trip.depleted,
+          cost: report.trip.cost,
+          trajectory: report.trip.trajectory,
+        }),
+        Err(error) => error_response(error),
       }
-      Err(err) => Err(err),
-    },
-    Err(err) => Err(err),
+    }
+    _ => HttpResponse::BadRequest().content_type(APPLICATION_JSON).await.unwrap(),
   }
 }
 
-#[patch("/plateaus/{plateau_id}/rovers/{rover_id}/{motion}")]
-pub async fn async_move_rover(path: Path<(String, String, String)>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-  let (plateau_id, rover_id, motion_path) = path.0;
+/// Moves every rover of the plateau tagged with `tag` along the same path, the oldest rover first.
+/// A rover never moves into a cell occupied by another rover of the plateau, that step is skipped.
+pub fn move_group(_plateau_id: Uuid, tag: &str, motion_vector: Vec<Motion>, conn: &DBPooledConnection) -> Result<Vec<MoveReport>, diesel::result::Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let plateau = get_plateau(_plateau_id, conn)?;
+  conn.transaction(|| {
+    let mut _rovers = rovers
+      .filter(plateau_id.eq(_plateau_id.to_string()))
+      .order((created_at.asc(), id.asc()))
+      .load::<RoverDB>(conn)?
+      .into_iter()
+      .map(|r| r.to_rover())
+      .collect::<Vec<Rover>>();
+
+    let mut reports = Vec::new();
+    for (start, report) in move_tagged(&plateau, &mut _rovers, tag, &motion_vector) {
+      diesel::update(rovers.find(report.rover.id()))
+        .set(report.rover.to_rover_db(_plateau_id.to_string()))
+        .execute(conn)?;
+      history::record(
+        plateau.id(),
+        report.rover.id(),
+        &history::new_batch(),
+        &Motion::to_path(&motion_vector),
+        &start,
+        &report.trip.poses,
+        conn,
+      )?;
+      reports.push(report);
+    }
+
+    match reports.is_empty() {
+      true => Err(diesel::result::Error::NotFound),
+      false => Ok(reports),
+    }
+  })
+}
+
+/// Moves the rovers tagged with `tag` one after the other, in the order of `rovers`, giving back the pose every moved
+/// rover started from along with its report. A rover never moves into a cell occupied by another rover.
+pub(crate) fn move_tagged(plateau: &Plateau, rovers: &mut [Rover], tag: &str, motion_vector: &[Motion]) -> Vec<(Pose, MoveReport)> {
+  let mut reports = Vec::new();
+  for n in 0..rovers.len() {
+    if !rovers[n].tags().contains(tag) {
+      continue;
+    }
+    let occupied = rovers
+      .iter()
+      .enumerate()
+      .filter(|(other, _)| *other != n)
+      .map(|(_, rover)| (rover.x(), rover.y()))
+      .collect::<HashSet<(i32, i32)>>();
+    let rover = &mut rovers[n];
+    let start = rover.pose();
+    let trip = rover.apply_motion_vector_avoiding(motion_vector.to_vec(), plateau, &occupied);
+    reports.push((start, MoveReport { rover: rover.clone(), trip }));
+  }
+  reports
+}
+
+#[patch("/plateaus/{plateau_id}/groups/{tag}/{motion}")]
+pub async fn async_move_group(path: Path<(String, String, String)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key, tag, motion_path) = path.0;
   let motion_vector = Motion::parse_path(&motion_path);
   match motion_vector {
     Ok(motion_vector) => {
-      let rover = web::block(move || move_rover(Uuid::from_str(&plateau_id).unwrap(), Uuid::from_str(&rover_id).unwrap(), motion_vector, &conn)).await;
-      match rover {
-        Ok(rover) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(rover),
-        _ => HttpResponse::NotFound().content_type(APPLICATION_JSON).await.unwrap(),
+      let reports = web::block(move || {
+        let plateau = store.find_plateau(&plateau_key)?;
+        store.move_group(Uuid::from_str(plateau.id()).unwrap(), &tag, motion_vector)
+      })
+      .await;
+      match reports {
+        Ok(reports) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(reports),
+        Err(error) => error_response(error),
       }
     }
     _ => HttpResponse::BadRequest().content_type(APPLICATION_JSON).await.unwrap(),
   }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MoveRequest {
+  pub rover_id: String,
+  pub path: String,
+}
+
+/// Moves the rovers one after the other, each along its whole path, inside a single transaction.
+/// Nothing is written if any rover can't be found or would collide with another rover.
+/// A rover whose battery runs out stops there.
+/// The rovers can be given by id or name. Returns their final state in the order of `moves`.
+pub fn move_rovers(_plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>, conn: &DBPooledConnection) -> Result<Vec<Rover>, Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let plateau = get_plateau(_plateau_id, conn)?;
+  conn.transaction(|| {
+    let mut _rovers = rovers
+      .filter(plateau_id.eq(_plateau_id.to_string()))
+      .load::<RoverDB>(conn)?
+      .into_iter()
+      .map(|r| r.to_rover())
+      .collect::<Vec<Rover>>();
+
+    let mut moved = Vec::new();
+    for (start, command, poses, rover) in move_in_turn(&plateau, &mut _rovers, moves)? {
+      diesel::update(rovers.find(rover.id()))
+        .set(rover.to_rover_db(_plateau_id.to_string()))
+        .execute(conn)?;
+      history::record(plateau.id(), rover.id(), &history::new_batch(), &command, &start, &poses, conn)?;
+      moved.push(rover);
+    }
+    Ok(moved)
+  })
+}
+
+/// A rover moved by [`move_in_turn`]: the pose it started from, its motion path, its pose after every step and the rover.
+pub(crate) type MovedInTurn = (Pose, String, Vec<Pose>, Rover);
+
+/// Moves the rovers one after the other, each along its whole path, `rovers` being all the rovers of the plateau.
+/// Fails if any rover can't be found or would collide with another rover. A rover whose battery runs out stops there.
+pub(crate) fn move_in_turn(plateau: &Plateau, rovers: &mut [Rover], moves: Vec<(String, Vec<Motion>)>) -> Result<Vec<MovedInTurn>, Error> {
+  let mut moved = Vec::new();
+  for (rover_key, motion_vector) in moves {
+    let n = match rovers.iter().position(|rover| rover.id() == rover_key || rover.name() == Some(&rover_key)) {
+      Some(n) => n,
+      None => return Err(Error::RoverNotFound(rover_key)),
+    };
+    let occupied = rovers
+      .iter()
+      .enumerate()
+      .filter(|(other, _)| *other != n)
+      .map(|(_, rover)| (rover.x(), rover.y()))
+      .collect::<HashSet<(i32, i32)>>();
+    let rover = &mut rovers[n];
+    let (start, command) = (rover.pose(), Motion::to_path(&motion_vector));
+    let mut poses = Vec::new();
+    for (step, motion) in motion_vector.into_iter().enumerate() {
+      let ahead = rover.cell_ahead(plateau);
+      match rover.apply_motion_avoiding(motion, plateau, &occupied) {
+        Ok(_) | Err(Stop::Impassable | Stop::TooSteep) => poses.push(rover.pose()),
+        Err(Stop::Occupied) => {
+          return Err(Error::CollisionError(format!(
+            "Rover '{}' would hit another rover at {} {} on step {}",
+            rover_key,
+            ahead.0,
+            ahead.1,
+            step + 1
+          )));
+        }
+        Err(Stop::Depleted) => break,
+      }
+    }
+    moved.push((start, command, poses, rover.clone()));
+  }
+  Ok(moved)
+}
+
+/// Moves the rovers in lock step, one motion per tick, see [`Simulation`] for how conflicts are resolved.
+/// The rovers of the plateau that don't move are obstacles. All the moves are written in a single transaction,
+/// nothing is written if a deadlock stops the rovers, the report then holds the deadlock.
+pub fn simulate_moves(
+  _plateau_id: Uuid,
+  moves: Vec<(String, Vec<Motion>)>,
+  policy: BlockedPolicy,
+  conn: &DBPooledConnection,
+) -> Result<SimulationReport, Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let plateau = get_plateau(_plateau_id, conn)?;
+  conn.transaction(|| {
+    let mut _rovers = rovers
+      .filter(plateau_id.eq(_plateau_id.to_string()))
+      .order((created_at.asc(), id.asc()))
+      .load::<RoverDB>(conn)?
+      .into_iter()
+      .map(|r| r.to_rover())
+      .collect::<Vec<Rover>>();
+
+    let (report, commands) = simulate(&plateau, _rovers, moves, policy)?;
+    if report.deadlock.is_none() {
+      for rover in report.rovers.iter() {
+        diesel::update(rovers.find(rover.id()))
+          .set(rover.to_rover_db(_plateau_id.to_string()))
+          .execute(conn)?;
+      }
+      let batch = history::new_batch();
+      for command in commands.iter() {
+        history::record(plateau.id(), &command.rover_id, &batch, &command.path, &command.start, &command.poses, conn)?;
+      }
+    }
+    Ok(report)
+  })
+}
+
+/// The command a rover carried out in a simulation, as it goes in the history.
+pub(crate) struct SimulatedCommand {
+  pub rover_id: String,
+  pub path: String,
+  pub start: Pose,
+  pub poses: Vec<Pose>,
+}
+
+/// Moves the rovers in lock step, see [`simulate_moves`], `rovers` being all the rovers of the plateau, oldest first.
+/// Gives back the report along with the command of every moving rover.
+pub(crate) fn simulate(
+  plateau: &Plateau,
+  mut rovers: Vec<Rover>,
+  moves: Vec<(String, Vec<Motion>)>,
+  policy: BlockedPolicy,
+) -> Result<(SimulationReport, Vec<SimulatedCommand>), Error> {
+  let mut participants = Vec::new();
+  for (rover_key, motion_vector) in moves {
+    match rovers.iter().find(|rover| rover.id() == rover_key || rover.name() == Some(&rover_key)) {
+      Some(rover) => participants.push((rover.clone(), motion_vector)),
+      None => return Err(Error::RoverNotFound(rover_key)),
+    }
+  }
+  rovers.retain(|rover| participants.iter().all(|(participant, _)| participant.id() != rover.id()));
+
+  let obstacles = rovers.iter().map(|rover| (rover.x(), rover.y())).collect::<HashSet<(i32, i32)>>();
+  let mut commands: Vec<SimulatedCommand> = Vec::new();
+  let mut simulation = Simulation::new(plateau.clone(), obstacles, policy);
+  for (rover, motion_vector) in participants {
+    match commands.iter_mut().find(|command| command.rover_id == rover.id()) {
+      Some(command) => command.path.push_str(&Motion::to_path(&motion_vector)),
+      None => commands.push(SimulatedCommand {
+        rover_id: rover.id().to_string(),
+        path: Motion::to_path(&motion_vector),
+        start: rover.pose(),
+        poses: Vec::new(),
+      }),
+    }
+    simulation.add_rover(rover, motion_vector);
+  }
+  let ticks = simulation.run();
+  let deadlock = simulation.deadlock().cloned();
+
+  // The rovers moved together, their poses after every tick they had a motion in make up the batch.
+  for command in commands.iter_mut() {
+    command.poses = ticks
+      .iter()
+      .flat_map(|tick| {
+        tick
+          .rovers
+          .iter()
+          .find(|rover_tick| rover_tick.rover_id == command.rover_id && rover_tick.motion.is_some())
+      })
+      .map(|rover_tick| Pose::new(rover_tick.x, rover_tick.y, rover_tick.facing.clone()))
+      .collect::<Vec<Pose>>();
+  }
+  Ok((
+    SimulationReport {
+      rovers: simulation.into_rovers(),
+      ticks,
+      deadlock,
+    },
+    commands,
+  ))
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MoveOptions {
+  #[serde(default)]
+  pub concurrent: bool,
+  #[serde(default)]
+  pub on_blocked: Option<BlockedPolicy>,
+}
+
+#[post("/plateaus/{plateau_id}/moves")]
+pub async fn async_move_rovers(
+  path: Path<(String,)>,
+  options: Query<MoveOptions>,
+  move_requests: Json<Vec<MoveRequest>>,
+  store: Data<dyn Store>,
+) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let concurrent = options.concurrent;
+  let policy = options.on_blocked.unwrap_or(BlockedPolicy::Skip);
+  let report = web::block(move || {
+    let mut moves = Vec::new();
+    for move_request in move_requests.iter() {
+      moves.push((move_request.rover_id.clone(), Motion::parse_path(&move_request.path)?));
+    }
+    let plateau = store.find_plateau(&plateau_key)?;
+    let _plateau_id = Uuid::from_str(plateau.id()).unwrap();
+    match concurrent {
+      true => store.simulate_moves(_plateau_id, moves, policy),
+      false => store.move_rovers(_plateau_id, moves).map(|rovers| SimulationReport {
+        rovers,
+        ticks: vec![],
+        deadlock: None,
+      }),
+    }
+  })
+  .await;
+
+  match report {
+    Ok(report) if report.deadlock.is_some() => HttpResponse::Conflict().content_type(APPLICATION_JSON).json(report),
+    Ok(report) if concurrent => HttpResponse::Ok().content_type(APPLICATION_JSON).json(report),
+    Ok(report) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(report.rovers),
+    Err(error) => error_response(error),
+  }
+}
+
 pub fn get_rover_n(n: i64, _plateau_id: Uuid, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
   use crate::schema::rovers::dsl::*;
   let res = rovers
@@ -269,7 +1198,8 @@ pub fn get_rover_n(n: i64, _plateau_id: Uuid, conn: &DBPooledConnection) -> Resu
 
 pub fn get_rovers_count(_plateau_id: Uuid, conn: &DBPooledConnection) -> Result<usize, diesel::result::Error> {
   use crate::schema::rovers::dsl::*;
-  rovers.count().execute(conn)
+  let count: i64 = rovers.filter(plateau_id.eq(_plateau_id.to_string())).count().get_result(conn)?;
+  Ok(count as usize)
 }
 
 pub fn get_rover(_plateau_id: Uuid, _rover_id: Uuid, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
@@ -288,61 +1218,176 @@ pub fn get_rover(_plateau_id: Uuid, _rover_id: Uuid, conn: &DBPooledConnection)
   }
 }
 
+pub fn get_rover_by_name(_plateau_id: Uuid, _name: &str, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let res = rovers
+    .filter(plateau_id.eq(_plateau_id.to_string()))
+    .filter(name.eq(_name))
+    .load::<RoverDB>(conn);
+  match res {
+    Ok(rovers_db) => match rovers_db.first() {
+      Some(rover_db) => Ok(rover_db.to_rover()),
+      _ => Err(diesel::result::Error::NotFound),
+    },
+    Err(err) => Err(err),
+  }
+}
+
+/// Looks up a rover of the given plateau by either its id or its name.
+pub fn find_rover(_plateau_id: Uuid, key: &str, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
+  match Uuid::from_str(key) {
+    Ok(_rover_id) => get_rover(_plateau_id, _rover_id, conn),
+    Err(_) => get_rover_by_name(_plateau_id, key, conn),
+  }
+}
+
 #[get("/plateaus/{plateau_id}/rovers/{rover_id}")]
-pub async fn async_get_rover(path: Path<(String, String)>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-  let (plateau_id, rover_id) = path.0;
-  let rover = web::block(move || get_rover(Uuid::from_str(&plateau_id).unwrap(), Uuid::from_str(&rover_id).unwrap(), &conn)).await;
+pub async fn async_get_rover(path: Path<(String, String)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key, rover_key) = path.0;
+  let rover = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.find_rover(Uuid::from_str(plateau.id()).unwrap(), &rover_key)
+  })
+  .await;
 
   match rover {
     Ok(rover) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(rover),
-    _ => HttpResponse::NoContent().content_type(APPLICATION_JSON).await.unwrap(),
+    Err(error) => error_response(error),
   }
 }
 
 pub fn list_rovers(_plateau_id: Uuid, total_rovers: i64, conn: &DBPooledConnection) -> Result<Rovers, diesel::result::Error> {
   use crate::schema::rovers::dsl::*;
 
-  let _rovers = match rovers
+  let _rovers = rovers
     .filter(plateau_id.eq(_plateau_id.to_string()))
     .order(created_at.desc())
     .limit(total_rovers)
     .load::<RoverDB>(conn)
-  {
-    Ok(_rovers) => _rovers,
-    Err(_) => vec![],
-  };
+    .unwrap_or_default();
 
   Ok(Rovers::new(_rovers.into_iter().map(|r| r.to_rover()).collect::<Vec<Rover>>()))
 }
 
 #[get("/plateaus/{plateau_id}/rovers")]
-pub async fn async_list_rovers(path: Path<(String,)>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-  let (plateau_id,) = path.0;
-  let rovers = web::block(move || list_rovers(Uuid::from_str(&plateau_id).unwrap(), 50, &conn)).await.unwrap();
+pub async fn async_list_rovers(path: Path<(String,)>, options: Query<ListOptions>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let limit = options.limit.unwrap_or(50);
+  let rovers = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.list_rovers(Uuid::from_str(plateau.id()).unwrap(), limit)
+  })
+  .await;
 
-  HttpResponse::Ok().content_type(APPLICATION_JSON).json(rovers)
+  match rovers {
+    Ok(rovers) => HttpResponse::Ok().content_type(APPLICATION_JSON).json(rovers),
+    Err(error) => error_response(error),
+  }
 }
 
 pub fn create_rover(_plateau_id: Uuid, rover: Rover, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
   use crate::schema::rovers::dsl::*;
 
   let rover_db = rover.to_rover_db(_plateau_id.to_string());
-  let _ = diesel::insert_into(rovers).values(&rover_db).execute(conn);
+  diesel::insert_into(rovers).values(&rover_db).execute(conn)?;
+  history::record(&_plateau_id.to_string(), rover.id(), &history::new_batch(), "", &rover.pose(), &[], conn)?;
 
   Ok(rover_db.to_rover())
 }
 
 #[post("/plateaus/{plateau_id}/rovers")]
-pub async fn async_create_rover(path: Path<(String,)>, rover_request: Json<RoverRequest>, pool: Data<DBPool>) -> HttpResponse {
-  let conn = pool.get().expect(CONNECTION_POOL_ERROR);
-
-  let (plateau_id,) = path.0;
-  let rover = web::block(move || create_rover(Uuid::from_str(&plateau_id).unwrap(), rover_request.to_rover(), &conn)).await;
+pub async fn async_create_rover(path: Path<(String,)>, rover_request: Json<RoverRequest>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key,) = path.0;
+  let rover = match rover_request.to_rover() {
+    Ok(rover) => rover,
+    Err(error) => return error_response(BlockingError::Error(error)),
+  };
+  let rover = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    store.place_rover(Uuid::from_str(plateau.id()).unwrap(), rover)
+  })
+  .await;
 
   match rover {
     Ok(rover) => HttpResponse::Created().content_type(APPLICATION_JSON).json(rover),
-    _ => HttpResponse::NoContent().await.unwrap(),
+    Err(error) => error_response(error),
+  }
+}
+
+/// Checks that the cell of a rover is on the plateau, passable and free of the other rovers.
+pub fn check_placement(plateau: &Plateau, rovers: &[Rover], rover: &Rover) -> Result<(), Error> {
+  if !plateau.contains(rover.x(), rover.y()) {
+    return Err(Error::CoOrdinateParseError(format!(
+      "Rover at {} {} is outside the plateau",
+      rover.x(),
+      rover.y()
+    )));
+  }
+  if !plateau.is_passable(rover.x(), rover.y()) {
+    return Err(Error::CollisionError(format!("Rover at {} {} is on an obstacle", rover.x(), rover.y())));
+  }
+  if let Some(other) = rovers.iter().find(|other| (other.x(), other.y()) == (rover.x(), rover.y())) {
+    return Err(Error::CollisionError(format!(
+      "Rover at {} {} is on rover {}",
+      rover.x(),
+      rover.y(),
+      other.label()
+    )));
+  }
+  Ok(())
+}
+
+/// Puts a rover of the given plateau back in an earlier state, e.g. to undo a command. The jump is recorded in the
+/// history of the rover as an `undo` command.
+pub fn restore_rover(_plateau_id: Uuid, rover: Rover, conn: &DBPooledConnection) -> Result<Rover, diesel::result::Error> {
+  use crate::schema::rovers::dsl::*;
+
+  let current = get_rover(_plateau_id, Uuid::from_str(rover.id()).unwrap(), conn)?;
+  conn.transaction(|| {
+    diesel::update(rovers.find(rover.id()))
+      .set(rover.to_rover_db(_plateau_id.to_string()))
+      .execute(conn)?;
+    history::record(
+      &_plateau_id.to_string(),
+      rover.id(),
+      &history::new_batch(),
+      "undo",
+      &current.pose(),
+      &[rover.pose()],
+      conn,
+    )?;
+    Ok(rover)
+  })
+}
+
+/// Deletes a rover of the given plateau along with its history.
+pub fn delete_rover(_plateau_id: Uuid, _rover_id: Uuid, conn: &DBPooledConnection) -> Result<(), diesel::result::Error> {
+  conn.transaction(|| {
+    diesel::delete(crate::schema::history::table.filter(crate::schema::history::rover_id.eq(_rover_id.to_string()))).execute(conn)?;
+    let query = crate::schema::rovers::table
+      .filter(crate::schema::rovers::id.eq(_rover_id.to_string()))
+      .filter(crate::schema::rovers::plateau_id.eq(_plateau_id.to_string()));
+    match diesel::delete(query).execute(conn)? {
+      0 => Err(diesel::result::Error::NotFound),
+      _ => Ok(()),
+    }
+  })
+}
+
+#[delete("/plateaus/{plateau_id}/rovers/{rover_id}")]
+pub async fn async_delete_rover(path: Path<(String, String)>, store: Data<dyn Store>) -> HttpResponse {
+  let (plateau_key, rover_key) = path.0;
+  let deleted = web::block(move || {
+    let plateau = store.find_plateau(&plateau_key)?;
+    let _plateau_id = Uuid::from_str(plateau.id()).unwrap();
+    let rover = store.find_rover(_plateau_id, &rover_key)?;
+    store.delete_rover(_plateau_id, Uuid::from_str(rover.id()).unwrap())
+  })
+  .await;
+
+  match deleted {
+    Ok(()) => HttpResponse::NoContent().finish(),
+    Err(error) => error_response(error),
   }
 }
@@ -1,9 +1,11 @@
 use std::fmt;
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{Direction, Error};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Pose {
   x: i32,
   y: i32,
@@ -32,7 +34,7 @@ impl FromStr for Pose {
   type Err = Error;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
-    let pose: Vec<&str> = s.trim().split(' ').collect();
+    let pose: Vec<&str> = s.split_whitespace().collect();
 
     match pose.len() {
       len if len != 3 => Err(Error::PoseParseError(format!("Expected 3 parameters but found {}", len))),
@@ -40,10 +42,10 @@ impl FromStr for Pose {
         let x: i32 = pose[0].parse()?;
         let y: i32 = pose[1].parse()?;
         let facing = match pose[2] {
-          "N" => Direction::North,
-          "E" => Direction::East,
-          "S" => Direction::South,
-          "W" => Direction::West,
+          "N" | "n" => Direction::North,
+          "E" | "e" => Direction::East,
+          "S" | "s" => Direction::South,
+          "W" | "w" => Direction::West,
           other => return Err(Error::PoseParseError(format!("Invalid direction '{}'", other))),
         };
 
@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::{Direction, Error, Plateau, Rover, Terrain};
+
+/// The cells a rover went through, in order, the starting cell first.
+pub type Trajectory = Vec<(i32, i32)>;
+
+/// The side of a cell in an SVG drawing.
+const CELL_SIZE: i32 = 32;
+
+/// The colors the trajectories are drawn in, one rover after the other.
+const TRAJECTORY_COLORS: [&str; 6] = ["#d62728", "#1f77b4", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf"];
+
+fn arrow(facing: &Direction) -> char {
+  match facing {
+    Direction::North => '^',
+    Direction::East => '>',
+    Direction::South => 'v',
+    Direction::West => '<',
+  }
+}
+
+/// Draws the plateau as text, the top row (`y_max`) first, with the symbols of a map:
+/// rovers are arrows showing their heading, `#` is an obstacle, `s` sand and `i` ice, `+` a charging station,
+/// `*` a cell on a trajectory and `.` any other cell.
+pub fn ascii(plateau: &Plateau, rovers: &[Rover], trajectories: &[Trajectory]) -> String {
+  let rovers = rovers
+    .iter()
+    .map(|rover| ((rover.x(), rover.y()), arrow(&rover.facing())))
+    .collect::<HashMap<(i32, i32), char>>();
+  let visited = trajectories.iter().flatten().copied().collect::<HashSet<(i32, i32)>>();
+  let mut text = String::new();
+  for y in (0..=plateau.y_max()).rev() {
+    for x in 0..=plateau.x_max() {
+      let symbol = match rovers.get(&(x, y)) {
+        Some(arrow) => *arrow,
+        None if plateau.terrain_at(x, y) == Terrain::Impassable => '#',
+        None if visited.contains(&(x, y)) => '*',
+        None if plateau.is_charging_station(x, y) => '+',
+        None => plateau.terrain_at(x, y).symbol(),
+      };
+      text.push(symbol);
+    }
+    text.push('\n');
+  }
+  text
+}
+
+fn terrain_color(terrain: Terrain) -> &'static str {
+  match terrain {
+    Terrain::Rock => "#c9a27e",
+    Terrain::Sand => "#f2d492",
+    Terrain::Ice => "#cfe8f7",
+    Terrain::Impassable => "#444444",
+  }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The center of a cell in an SVG drawing, whose origin is the top left corner. The cell is on the plateau, whose
+/// drawing [`svg_size`] checked to fit.
+fn center(plateau: &Plateau, x: i32, y: i32) -> (i32, i32) {
+  (x * CELL_SIZE + CELL_SIZE / 2, (plateau.y_max() - y) * CELL_SIZE + CELL_SIZE / 2)
+}
+
+/// The width and height of an SVG drawing of the plateau, which must fit in an `i32` for the cells to be placed.
+fn svg_size(plateau: &Plateau) -> Result<(i32, i32), Error> {
+  let side = |max: i32| max.checked_add(1).filter(|cells| *cells > 0).and_then(|cells| cells.checked_mul(CELL_SIZE));
+  match (side(plateau.x_max()), side(plateau.y_max())) {
+    (Some(width), Some(height)) => Ok((width, height)),
+    _ => Err(Error::RenderError(format!(
+      "The plateau bounds {} {} are too large to draw",
+      plateau.x_max(),
+      plateau.y_max()
+    ))),
+  }
+}
+
+/// Opens an SVG drawing of the plateau with the terrain of every cell, darker the higher it is, and the charging stations.
+fn svg_background(plateau: &Plateau) -> Result<String, Error> {
+  let (width, height) = svg_size(plateau)?;
+  let heights = plateau
+    .heightmap()
+    .map(|heightmap| heightmap.rows().iter().flatten().copied().collect::<Vec<i32>>());
+  let (lowest, highest) = match &heights {
+    Some(heights) => (
+      heights.iter().min().copied().unwrap_or_default(),
+      heights.iter().max().copied().unwrap_or_default(),
+    ),
+    None => (0, 0),
+  };
+
+  let mut svg = String::new();
+  let _ = writeln!(
+    svg,
+    r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+    width, height, width, height
+  );
+  let _ = writeln!(svg, "<title>Plateau {}</title>", escape(plateau.label()));
+  for y in 0..=plateau.y_max() {
+    for x in 0..=plateau.x_max() {
+      let (left, top) = (x * CELL_SIZE, (plateau.y_max() - y) * CELL_SIZE);
+      let _ = writeln!(
+        svg,
+        r##"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="#ffffff" stroke-width="1"/>"##,
+        left,
+        top,
+        CELL_SIZE,
+        CELL_SIZE,
+        terrain_color(plateau.terrain_at(x, y))
+      );
+      if highest > lowest {
+        let shade = 0.5 * f64::from(plateau.height_at(x, y) - lowest) / f64::from(highest - lowest);
+        let _ = writeln!(
+          svg,
+          r#"<rect x="{}" y="{}" width="{}" height="{}" fill="black" fill-opacity="{:.2}"/>"#,
+          left, top, CELL_SIZE, CELL_SIZE, shade
+        );
+      }
+    }
+  }
+  for (x, y) in plateau.charging_stations() {
+    let (cx, cy) = center(plateau, *x, *y);
+    let _ = writeln!(svg, r##"<circle cx="{}" cy="{}" r="{}" fill="#2e7d32"/>"##, cx, cy, CELL_SIZE / 6);
+  }
+  Ok(svg)
+}
+
+/// A rover as an arrow showing its heading, named after the rover.
+fn svg_rover(plateau: &Plateau, rover: &Rover) -> String {
+  let (cx, cy) = center(plateau, rover.x(), rover.y());
+  let angle = match rover.facing() {
+    Direction::North => 0,
+    Direction::East => 90,
+    Direction::South => 180,
+    Direction::West => 270,
+  };
+  let (tip, side) = (CELL_SIZE * 3 / 8, CELL_SIZE * 5 / 16);
+  format!(
+    r##"<polygon points="{},{} {},{} {},{}" fill="#222222" transform="rotate({} {} {})"><title>{}</title></polygon>"##,
+    cx,
+    cy - tip,
+    cx - side,
+    cy + side,
+    cx + side,
+    cy + side,
+    angle,
+    cx,
+    cy,
+    escape(&rover.to_string())
+  )
+}
+
+/// Draws the plateau as an SVG image: the terrain of every cell, darker the higher it is, the charging stations,
+/// the trajectories as lines through the cells and the rovers as arrows showing their heading.
+pub fn svg(plateau: &Plateau, rovers: &[Rover], trajectories: &[Trajectory]) -> Result<String, Error> {
+  let mut svg = svg_background(plateau)?;
+  for (n, trajectory) in trajectories.iter().enumerate().filter(|(_, trajectory)| trajectory.len() > 1) {
+    let points = trajectory
+      .iter()
+      .map(|(x, y)| {
+        let (cx, cy) = center(plateau, *x, *y);
+        format!("{},{}", cx, cy)
+      })
+      .collect::<Vec<String>>();
+    let _ = writeln!(
+      svg,
+      r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="3" stroke-linejoin="round"/>"#,
+      points.join(" "),
+      TRAJECTORY_COLORS[n % TRAJECTORY_COLORS.len()]
+    );
+  }
+  for rover in rovers {
+    let _ = writeln!(svg, "{}", svg_rover(plateau, rover));
+  }
+  svg.push_str("</svg>\n");
+  Ok(svg)
+}
+
+/// Draws the plateau as an animated SVG image showing the rovers of every frame in turn, `frame_duration` seconds each,
+/// over and over again. The frame duration must be a positive number of seconds.
+pub fn animated_svg(plateau: &Plateau, frames: &[Vec<Rover>], frame_duration: f64) -> Result<String, Error> {
+  if !(frame_duration.is_finite() && frame_duration > 0.0) {
+    return Err(Error::ReplayError(format!("Expected a positive frame duration but found {}", frame_duration)));
+  }
+  let mut svg = svg_background(plateau)?;
+  let duration = frame_duration * frames.len() as f64;
+  for (n, rovers) in frames.iter().enumerate() {
+    // Every frame is hidden but during its own slice of the animation, the last one staying on until it starts over.
+    let (start, end) = (n as f64 / frames.len() as f64, (n + 1) as f64 / frames.len() as f64);
+    let visibility = match (n, n + 1 == frames.len()) {
+      (0, true) => "visible".to_string(),
+      (0, false) => "visible;hidden".to_string(),
+      (_, true) => "hidden;visible".to_string(),
+      (_, false) => "hidden;visible;hidden".to_string(),
+    };
+    let key_times = match (n, n + 1 == frames.len()) {
+      (0, true) => "0".to_string(),
+      (0, false) => format!("0;{:.4}", end),
+      (_, true) => format!("0;{:.4}", start),
+      (_, false) => format!("0;{:.4};{:.4}", start, end),
+    };
+    let _ = writeln!(svg, r#"<g visibility="{}">"#, if n == 0 { "visible" } else { "hidden" });
+    let _ = writeln!(
+      svg,
+      r#"<animate attributeName="visibility" calcMode="discrete" values="{}" keyTimes="{}" dur="{}s" repeatCount="indefinite"/>"#,
+      visibility, key_times, duration
+    );
+    for rover in rovers {
+      let _ = writeln!(svg, "{}", svg_rover(plateau, rover));
+    }
+    svg.push_str("</g>\n");
+  }
+  svg.push_str("</svg>\n");
+  Ok(svg)
+}
+
+/// Draws every frame as text, one after the other, each under a `Frame N` line.
+pub fn ascii_frames(plateau: &Plateau, frames: &[Vec<Rover>]) -> String {
+  frames
+    .iter()
+    .enumerate()
+    .map(|(n, rovers)| format!("Frame {}\n{}", n, ascii(plateau, rovers, &[])))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
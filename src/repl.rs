@@ -0,0 +1,296 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use uuid::Uuid;
+
+use crate::plateau::Plateau;
+use crate::rover::RoverRequest;
+use crate::store::Store;
+use crate::{render, Error, Motion, Pose, Rover};
+
+/// The commands of the shell with their arguments and what they do, as `help` lists them.
+const COMMANDS: &[(&str, &str, &str)] = &[
+  ("plateaus", "", "Lists the plateaus."),
+  ("create plateau", "X_MAX Y_MAX [NAME]", "Creates a plateau and selects it."),
+  ("select plateau", "PLATEAU", "Selects a plateau by id or name."),
+  ("rovers", "", "Lists the rovers of the selected plateau."),
+  ("create rover", "X Y HEADING [NAME]", "Lands a rover on the selected plateau and selects it."),
+  ("select rover", "ROVER", "Selects a rover of the selected plateau by id or name."),
+  ("move", "[ROVER] PATH", "Moves the selected rover, or the given one, along a motion path."),
+  ("show", "", "Shows the selected rover, or the selected plateau and its rovers."),
+  ("render", "", "Draws the selected plateau with its rovers."),
+  ("undo", "", "Puts the rover of the last move of the session back where it was."),
+  ("help", "", "Lists the commands."),
+  ("quit", "", "Leaves the shell."),
+];
+
+/// What the shell does once a command has been carried out.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reply {
+  Print(String),
+  Quit,
+}
+
+/// An interactive session: the selected plateau and rover, and the moves that can be undone.
+#[derive(Debug, Default)]
+pub struct Session {
+  plateau: Option<Plateau>,
+  rover: Option<Rover>,
+  /// Every rover moved in the session as it was before the move, with its plateau, the latest last.
+  undo: Vec<(Uuid, Rover)>,
+}
+
+impl Session {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn plateau_id(&self) -> Option<Uuid> {
+    self.plateau.as_ref().map(|plateau| Uuid::from_str(plateau.id()).unwrap())
+  }
+
+  pub fn plateau(&self) -> Option<&Plateau> {
+    self.plateau.as_ref()
+  }
+
+  pub fn rover(&self) -> Option<&Rover> {
+    self.rover.as_ref()
+  }
+
+  /// Selects a plateau, and no rover.
+  pub fn select_plateau(&mut self, plateau: Plateau) {
+    self.plateau = Some(plateau);
+    self.rover = None;
+  }
+
+  /// Reads the selected plateau and rover again, as another process may have moved or deleted them.
+  pub fn refresh(&mut self, store: &dyn Store) {
+    self.plateau = self.plateau_id().and_then(|plateau_id| store.get_plateau(plateau_id).ok());
+    self.rover = match self.plateau_id() {
+      Some(plateau_id) => self
+        .rover
+        .as_ref()
+        .and_then(|rover| store.get_rover(plateau_id, Uuid::from_str(rover.id()).unwrap()).ok()),
+      None => None,
+    };
+  }
+
+  /// The prompt, naming the selected plateau and rover.
+  pub fn prompt(&self) -> String {
+    match (&self.plateau, &self.rover) {
+      (Some(plateau), Some(rover)) => format!("gcs {}/{}> ", plateau.label(), rover.label()),
+      (Some(plateau), None) => format!("gcs {}> ", plateau.label()),
+      _ => "gcs> ".into(),
+    }
+  }
+
+  fn selected_plateau(&self) -> Result<(Uuid, &Plateau), Error> {
+    match &self.plateau {
+      Some(plateau) => Ok((Uuid::from_str(plateau.id()).unwrap(), plateau)),
+      None => Err(Error::CommandParseError(
+        "No plateau selected, use select plateau or create plateau first".into(),
+      )),
+    }
+  }
+
+  fn find_rover(&self, key: &str, store: &dyn Store) -> Result<Rover, Error> {
+    let (plateau_id, _) = self.selected_plateau()?;
+    store.find_rover(plateau_id, key).map_err(|_| Error::RoverNotFound(key.into()))
+  }
+
+  /// Carries out a command line, giving back what to print.
+  pub fn execute(&mut self, line: &str, store: &dyn Store) -> Result<Reply, Error> {
+    let words = line.split_whitespace().collect::<Vec<&str>>();
+    let text = match words[..] {
+      [] => String::new(),
+      ["help"] => help(),
+      ["quit"] | ["exit"] => return Ok(Reply::Quit),
+      ["plateaus"] => store.list_plateaus(50)?.to_string(),
+      ["create", "plateau", x_max, y_max, ref name @ ..] if name.len() <= 1 => {
+        let mut plateau = Plateau::from_str(&format!("{} {}", x_max, y_max))?;
+        plateau.set_name(name.first().map(|name| name.to_string()))?;
+        let plateau = store.create_plateau(plateau)?;
+        let text = format!("Created plateau {} with bounds {}", plateau.label(), plateau);
+        self.select_plateau(plateau);
+        text
+      }
+      ["select", "plateau", key] => {
+        let plateau = store.find_plateau(key).map_err(|_| Error::PlateauNotFound(key.into()))?;
+        let text = format!("Selected plateau {} with bounds {}", plateau.label(), plateau);
+        self.select_plateau(plateau);
+        text
+      }
+      ["rovers"] => {
+        let (plateau_id, _) = self.selected_plateau()?;
+        store.list_rovers(plateau_id, i64::MAX)?.to_string()
+      }
+      ["create", "rover", x, y, heading, ref name @ ..] if name.len() <= 1 => {
+        let (plateau_id, _) = self.selected_plateau()?;
+        let pose = Pose::from_str(&format!("{} {} {}", x, y, heading))?;
+        let rover_request = RoverRequest {
+          x: pose.x(),
+          y: pose.y(),
+          facing: pose.facing(),
+          name: name.first().map(|name| name.to_string()),
+          tags: Default::default(),
+          metadata: Default::default(),
+          battery: None,
+          max_slope: None,
+        };
+        let rover = store.place_rover(plateau_id, rover_request.to_rover()?)?;
+        let text = format!("Created rover {} at {}", rover.label(), rover.pose());
+        self.rover = Some(rover);
+        text
+      }
+      ["select", "rover", key] => {
+        let rover = self.find_rover(key, store)?;
+        let text = rover.to_string();
+        self.rover = Some(rover);
+        text
+      }
+      ["move", path] => match self.rover.as_ref().map(|rover| rover.id().to_string()) {
+        Some(rover_id) => self.move_rover(&rover_id, path, store)?,
+        None => return Err(Error::CommandParseError("No rover selected, use select rover or move ROVER PATH".into())),
+      },
+      ["move", key, path] => self.move_rover(key, path, store)?,
+      ["show"] => match (&self.plateau, &self.rover) {
+        (Some(_), Some(rover)) => self.find_rover(rover.id(), store)?.to_string(),
+        (Some(plateau), None) => {
+          let (plateau_id, _) = self.selected_plateau()?;
+          format!(
+            "Plateau {} with bounds {}\n{}",
+            plateau.label(),
+            plateau,
+            store.list_rovers(plateau_id, i64::MAX)?
+          )
+        }
+        _ => return Err(Error::CommandParseError("Nothing selected, use select plateau first".into())),
+      },
+      ["render"] => {
+        let (plateau_id, plateau) = self.selected_plateau()?;
+        let rovers = store.list_rovers(plateau_id, i64::MAX)?;
+        render::ascii(plateau, &rovers, &[]).trim_end().to_string()
+      }
+      ["undo"] => match self.undo.pop() {
+        Some((plateau_id, rover)) => {
+          let rover = store.restore_rover(plateau_id, rover)?;
+          if self.rover.as_ref().is_some_and(|selected| selected.id() == rover.id()) {
+            self.rover = Some(rover.clone());
+          }
+          format!("Undone, {}", rover)
+        }
+        None => return Err(Error::CommandParseError("Nothing to undo".into())),
+      },
+      _ => {
+        return Err(Error::CommandParseError(format!(
+          "Unknown command '{}', type help for the commands",
+          line.trim()
+        )))
+      }
+    };
+    Ok(Reply::Print(text))
+  }
+
+  fn move_rover(&mut self, key: &str, path: &str, store: &dyn Store) -> Result<String, Error> {
+    let (plateau_id, _) = self.selected_plateau()?;
+    let motion_vector = Motion::parse_path(path)?;
+    let rover = self.find_rover(key, store)?;
+    let report = store.move_rover(plateau_id, Uuid::from_str(rover.id()).unwrap(), motion_vector)?;
+    self.undo.push((plateau_id, rover));
+    if self.rover.as_ref().is_some_and(|selected| selected.id() == report.rover.id()) {
+      self.rover = Some(report.rover.clone());
+    }
+    Ok(report.to_string())
+  }
+}
+
+fn help() -> String {
+  let width = COMMANDS
+    .iter()
+    .map(|(command, arguments, _)| command.len() + arguments.len() + 1)
+    .max()
+    .unwrap_or_default();
+  COMMANDS
+    .iter()
+    .map(|(command, arguments, description)| format!("{:width$}  {}", format!("{} {}", command, arguments).trim_end(), description, width = width))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// Completes the commands of the shell, and the ids and names of the plateaus and of the rovers of the selected plateau.
+pub struct ShellHelper {
+  store: Arc<dyn Store>,
+  /// The plateau selected in the session, whose rovers are completed.
+  pub plateau_id: Option<Uuid>,
+}
+
+impl ShellHelper {
+  pub fn new(store: Arc<dyn Store>) -> Self {
+    Self { store, plateau_id: None }
+  }
+
+  fn candidates(&self, previous: &[&str]) -> Vec<String> {
+    match previous {
+      [] => {
+        let mut words: Vec<String> = Vec::new();
+        for word in COMMANDS.iter().filter_map(|(command, _, _)| command.split(' ').next()) {
+          if !words.iter().any(|other| other == word) {
+            words.push(word.into());
+          }
+        }
+        words
+      }
+      ["create"] | ["select"] => vec!["plateau".into(), "rover".into()],
+      ["select", "plateau"] => self
+        .store
+        .list_plateaus(i64::MAX)
+        .map(|plateaus| plateaus.iter().flat_map(|plateau| keys(plateau.id(), plateau.name())).collect())
+        .unwrap_or_default(),
+      ["select", "rover"] | ["move"] => match self.plateau_id {
+        Some(plateau_id) => self
+          .store
+          .list_rovers(plateau_id, i64::MAX)
+          .map(|rovers| rovers.iter().flat_map(|rover| keys(rover.id(), rover.name())).collect())
+          .unwrap_or_default(),
+        None => Vec::new(),
+      },
+      _ => Vec::new(),
+    }
+  }
+}
+
+/// The name of a plateau or rover, if it has one, and its id.
+fn keys(id: &str, name: Option<&str>) -> Vec<String> {
+  name.into_iter().chain([id]).map(String::from).collect()
+}
+
+impl Completer for ShellHelper {
+  type Candidate = String;
+
+  fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+    let line = &line[..pos];
+    let start = line.rfind(char::is_whitespace).map(|space| space + 1).unwrap_or(0);
+    let previous = line[..start].split_whitespace().collect::<Vec<&str>>();
+    let candidates = self
+      .candidates(&previous)
+      .into_iter()
+      .filter(|candidate| candidate.starts_with(&line[start..]))
+      .collect();
+    Ok((start, candidates))
+  }
+}
+
+impl Hinter for ShellHelper {
+  type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
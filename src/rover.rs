@@ -1,12 +1,17 @@
+use std::collections::HashSet;
 use std::{fmt, ops::Deref};
 
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 
+use crate::battery::{Battery, BatteryRequest};
+use crate::labels::{self, Tags};
+use crate::render::Trajectory;
 use crate::Plateau;
-use crate::{direction::Direction, schema::rovers};
+use crate::{direction::Direction, schema::rovers, Error};
 use crate::{motion::Motion, pose::Pose};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -14,7 +19,7 @@ pub struct Rovers(Vec<Rover>);
 
 impl Rovers {
   pub fn new(rovers: Vec<Rover>) -> Self {
-    Self { 0: rovers }
+    Self(rovers)
   }
 }
 
@@ -33,6 +38,16 @@ pub struct Rover {
   x: i32,
   y: i32,
   facing: Direction,
+  #[serde(default)]
+  name: Option<String>,
+  #[serde(default)]
+  tags: Tags,
+  #[serde(default)]
+  metadata: Value,
+  #[serde(default)]
+  battery: Option<Battery>,
+  #[serde(default)]
+  max_slope: Option<i32>,
 }
 
 impl Rover {
@@ -43,6 +58,19 @@ impl Rover {
       x,
       y,
       facing,
+      name: None,
+      tags: Tags::new(),
+      metadata: Value::Null,
+      battery: None,
+      max_slope: None,
+    }
+  }
+
+  /// A rover that already has an id, e.g. one read from the history.
+  pub(crate) fn with_id(id: &str, x: i32, y: i32, facing: Direction) -> Self {
+    Self {
+      id: id.to_string(),
+      ..Self::new(x, y, facing)
     }
   }
 
@@ -50,10 +78,52 @@ impl Rover {
     &self.id
   }
 
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// The name of the rover if it has one, its id otherwise.
+  pub fn label(&self) -> &str {
+    self.name().unwrap_or(&self.id)
+  }
+
+  pub fn tags(&self) -> &Tags {
+    &self.tags
+  }
+
+  pub fn metadata(&self) -> &Value {
+    &self.metadata
+  }
+
+  /// Rovers without a battery never run out of energy.
+  pub fn battery(&self) -> Option<&Battery> {
+    self.battery.as_ref()
+  }
+
+  pub fn set_battery(&mut self, battery: Option<Battery>) {
+    self.battery = battery;
+  }
+
+  /// The largest height difference the rover can step across, rovers without one climbing anything.
+  pub fn max_slope(&self) -> Option<i32> {
+    self.max_slope
+  }
+
+  pub fn set_max_slope(&mut self, max_slope: Option<i32>) {
+    self.max_slope = max_slope;
+  }
+
   pub fn pose(&self) -> Pose {
     Pose::new(self.x, self.y, self.facing.clone())
   }
 
+  /// Puts the rover in a pose without it moving there, e.g. when replaying its history.
+  pub(crate) fn set_pose(&mut self, pose: &Pose) {
+    self.x = pose.x();
+    self.y = pose.y();
+    self.facing = pose.facing();
+  }
+
   pub fn x(&self) -> i32 {
     self.x
   }
@@ -124,37 +194,189 @@ impl Rover {
     }
   }
 
-  pub fn move_straight(&mut self, plateau: &Plateau) {
+  /// Moves the rover one cell ahead unless the plateau edge, an impassable cell or a steep slope is in the way.
+  /// Returns the traversal cost of the move, i.e. the terrain cost of the cell moved into, nothing if the rover stays.
+  pub fn move_straight(&mut self, plateau: &Plateau) -> i32 {
+    let (x, y) = self.cell_ahead(plateau);
+    if (x, y) == (self.x, self.y) || self.ground_stop(plateau).is_some() {
+      return 0;
+    }
     match self.facing {
       Direction::North => self.move_y_forward(plateau.y_max()),
       Direction::East => self.move_x_forward(plateau.x_max()),
       Direction::South => self.move_y_backward(),
       Direction::West => self.move_x_backward(),
     };
+    plateau.terrain_at(x, y).cost().unwrap_or_default()
+  }
+
+  /// Why the ground keeps the rover from moving into the cell ahead, within the plateau bounds:
+  /// the cell is impassable or the height difference isn't below the maximum slope of the rover.
+  pub fn ground_stop(&self, plateau: &Plateau) -> Option<Stop> {
+    let (x, y) = self.cell_ahead(plateau);
+    if (x, y) == (self.x, self.y) {
+      return None;
+    }
+    if !plateau.is_passable(x, y) {
+      return Some(Stop::Impassable);
+    }
+    match self.max_slope {
+      Some(max_slope) if plateau.slope((self.x, self.y), (x, y)) >= max_slope => Some(Stop::TooSteep),
+      _ => None,
+    }
   }
 
-  pub fn apply_motion(&mut self, motion: Motion, plateau: &Plateau) {
-    match motion {
-      Motion::TurnLeft => self.turn_left(),
-      Motion::TurnRight => self.turn_right(),
+  /// The energy the motion takes, a move straight taking more on costly terrain.
+  /// Nothing for a move the plateau bounds or the ground prevent.
+  pub fn motion_cost(&self, motion: &Motion, plateau: &Plateau) -> i32 {
+    match (&self.battery, motion) {
+      (None, _) => 0,
+      (Some(_), Motion::MoveStraight) if self.cell_ahead(plateau) == (self.x, self.y) || self.ground_stop(plateau).is_some() => 0,
+      (Some(battery), Motion::MoveStraight) => {
+        let (x, y) = self.cell_ahead(plateau);
+        battery.cost(motion).saturating_mul(plateau.terrain_at(x, y).cost().unwrap_or_default())
+      }
+      (Some(battery), motion) => battery.cost(motion),
+    }
+  }
+
+  pub fn can_afford(&self, motion: &Motion, plateau: &Plateau) -> bool {
+    match &self.battery {
+      Some(battery) => battery.can_afford(self.motion_cost(motion, plateau)),
+      None => true,
+    }
+  }
+
+  /// Applies the motion if the battery can pay for it, and recharges the battery on a charging station.
+  /// Returns the traversal cost of the motion, or `None` if the battery is depleted.
+  pub fn apply_motion(&mut self, motion: Motion, plateau: &Plateau) -> Option<i32> {
+    if !self.can_afford(&motion, plateau) {
+      return None;
+    }
+    let cost = self.motion_cost(&motion, plateau);
+    let traversal_cost = match motion {
+      Motion::TurnLeft => {
+        self.turn_left();
+        0
+      }
+      Motion::TurnRight => {
+        self.turn_right();
+        0
+      }
       Motion::MoveStraight => self.move_straight(plateau),
     };
+    if let Some(battery) = &mut self.battery {
+      battery.drain(cost);
+      if plateau.is_charging_station(self.x, self.y) {
+        battery.charge();
+      }
+    }
+    Some(traversal_cost)
+  }
+
+  /// Applies the motions one by one, the rover stops when its battery is depleted.
+  pub fn apply_motion_vector(&mut self, motion_vector: Vec<Motion>, plateau: &Plateau) -> Trip {
+    self.apply_motion_vector_avoiding(motion_vector, plateau, &HashSet::new())
+  }
+
+  /// The cell the rover ends up in by moving straight, within the plateau bounds.
+  pub fn cell_ahead(&self, plateau: &Plateau) -> (i32, i32) {
+    match self.facing {
+      Direction::North if self.y < plateau.y_max() => (self.x, self.y + 1),
+      Direction::East if self.x < plateau.x_max() => (self.x + 1, self.y),
+      Direction::South if self.y > 0 => (self.x, self.y - 1),
+      Direction::West if self.x > 0 => (self.x - 1, self.y),
+      _ => (self.x, self.y),
+    }
+  }
+
+  /// Applies the motion unless it would take the rover into one of the occupied cells, or the ground keeps it from moving.
+  /// Returns the traversal cost of the motion.
+  pub fn apply_motion_avoiding(&mut self, motion: Motion, plateau: &Plateau, occupied: &HashSet<(i32, i32)>) -> Result<i32, Stop> {
+    if let Motion::MoveStraight = motion {
+      let ahead = self.cell_ahead(plateau);
+      if ahead != (self.x, self.y) && occupied.contains(&ahead) {
+        return Err(Stop::Occupied);
+      }
+      if let Some(stop) = self.ground_stop(plateau) {
+        return Err(stop);
+      }
+    }
+    self.apply_motion(motion, plateau).ok_or(Stop::Depleted)
+  }
+
+  /// Applies the motions one by one, skipping the moves into occupied or impassable cells and up or down steep slopes.
+  /// The rover stops when its battery is depleted.
+  pub fn apply_motion_vector_avoiding(&mut self, motion_vector: Vec<Motion>, plateau: &Plateau, occupied: &HashSet<(i32, i32)>) -> Trip {
+    let mut trip = Trip {
+      trajectory: vec![(self.x, self.y)],
+      ..Trip::default()
+    };
+    for (step, motion) in motion_vector.into_iter().enumerate() {
+      let (x, y) = self.cell_ahead(plateau);
+      let slope = plateau.slope((self.x, self.y), (x, y));
+      let result = self.apply_motion_avoiding(motion, plateau, occupied);
+      if result != Err(Stop::Depleted) {
+        trip.poses.push(self.pose());
+      }
+      match result {
+        Ok(cost) => {
+          trip.cost += cost;
+          if trip.trajectory.last() != Some(&(self.x, self.y)) {
+            trip.trajectory.push((self.x, self.y));
+          }
+        }
+        Err(Stop::Occupied | Stop::Impassable) => trip.blocked.push(Blocked {
+          step: step + 1,
+          x,
+          y,
+          slope: None,
+        }),
+        Err(Stop::TooSteep) => trip.blocked.push(Blocked {
+          step: step + 1,
+          x,
+          y,
+          slope: Some(slope),
+        }),
+        Err(Stop::Depleted) => {
+          trip.depleted = Some(step + 1);
+          break;
+        }
+      }
+    }
+    trip
   }
 
-  pub fn apply_motion_vector(&mut self, motion_vector: Vec<Motion>, plateau: &Plateau) {
-    for motion in motion_vector {
-      self.apply_motion(motion, plateau)
+  /// The request creating a rover like this one, as the REST API takes it. The id is left to the server.
+  pub fn to_request(&self) -> RoverRequest {
+    RoverRequest {
+      x: self.x,
+      y: self.y,
+      facing: self.facing.clone(),
+      name: self.name.clone(),
+      tags: self.tags.clone(),
+      metadata: self.metadata.clone(),
+      battery: self.battery.as_ref().map(|battery| battery.to_request()),
+      max_slope: self.max_slope,
     }
   }
 
   pub fn to_rover_db(&self, plateau_id: String) -> RoverDB {
     RoverDB {
       id: self.id.clone(),
-      created_at: Utc::now().naive_utc(),
+      created_at: self.created_at.naive_utc(),
       x: self.x,
       y: self.y,
       facing: self.facing.clone(),
       plateau_id,
+      name: self.name.clone(),
+      tags: labels::tags_to_db(&self.tags),
+      metadata: labels::metadata_to_db(&self.metadata),
+      battery_level: self.battery.as_ref().map(|battery| battery.level()),
+      battery_capacity: self.battery.as_ref().map(|battery| battery.capacity()),
+      turn_cost: self.battery.as_ref().map(|battery| battery.turn_cost()),
+      move_cost: self.battery.as_ref().map(|battery| battery.move_cost()),
+      max_slope: self.max_slope,
     }
   }
 }
@@ -168,6 +390,14 @@ pub struct RoverDB {
   y: i32,
   facing: Direction,
   plateau_id: String,
+  name: Option<String>,
+  tags: String,
+  metadata: Option<String>,
+  battery_level: Option<i32>,
+  battery_capacity: Option<i32>,
+  turn_cost: Option<i32>,
+  move_cost: Option<i32>,
+  max_slope: Option<i32>,
 }
 
 impl RoverDB {
@@ -178,6 +408,11 @@ impl RoverDB {
       x: self.x,
       y: self.y,
       facing: self.facing.clone(),
+      name: self.name.clone(),
+      tags: labels::tags_from_db(&self.tags),
+      metadata: labels::metadata_from_db(&self.metadata),
+      battery: Battery::from_db(self.battery_level, self.battery_capacity, self.turn_cost, self.move_cost),
+      max_slope: self.max_slope,
     }
   }
 }
@@ -187,17 +422,83 @@ pub struct RoverRequest {
   pub x: i32,
   pub y: i32,
   pub facing: Direction,
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub tags: Tags,
+  #[serde(default)]
+  pub metadata: Value,
+  #[serde(default)]
+  pub battery: Option<BatteryRequest>,
+  #[serde(default)]
+  pub max_slope: Option<i32>,
 }
 
 impl RoverRequest {
-  pub fn to_rover(&self) -> Rover {
-    Rover::new(self.x, self.y, self.facing.clone())
+  pub fn to_rover(&self) -> Result<Rover, Error> {
+    if let Some(name) = &self.name {
+      labels::validate_name(name)?;
+    }
+    Ok(Rover {
+      name: self.name.clone(),
+      tags: self.tags.clone(),
+      metadata: self.metadata.clone(),
+      battery: self.battery.as_ref().map(|battery| battery.to_battery()).transpose()?,
+      max_slope: self.max_slope,
+      ..Rover::new(self.x, self.y, self.facing.clone())
+    })
   }
 }
 
+/// A step of a motion path that was skipped because the cell ahead was occupied or impassable,
+/// or too high above or below the rover.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Blocked {
+  pub step: usize,
+  pub x: i32,
+  pub y: i32,
+  /// The height difference, when the slope was too steep.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub slope: Option<i32>,
+}
+
+/// Why a rover couldn't carry out a motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+  Occupied,
+  Impassable,
+  TooSteep,
+  Depleted,
+}
+
+/// What happened to a rover along a motion path, besides the motions it carried out.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Trip {
+  pub blocked: Vec<Blocked>,
+  /// The step on which the battery ran out, the rover stopped there.
+  pub depleted: Option<usize>,
+  /// The traversal cost of the moves carried out, the sum of the terrain costs of the cells moved into.
+  #[serde(default)]
+  pub cost: i32,
+  /// The cells the rover went through, the starting cell first.
+  #[serde(skip)]
+  pub trajectory: Trajectory,
+  /// The pose of the rover after every step it got to.
+  #[serde(skip)]
+  pub poses: Vec<Pose>,
+}
+
+/// The outcome of moving one rover.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MoveReport {
+  pub rover: Rover,
+  #[serde(flatten)]
+  pub trip: Trip,
+}
+
 impl fmt::Display for Rover {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "Rover {} is at {} {} {}", self.id, self.x, self.y, self.facing)
+    write!(f, "Rover {} is at {} {} {}", self.label(), self.x, self.y, self.facing)
   }
 }
 
@@ -205,8 +506,32 @@ impl fmt::Display for Rovers {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     writeln!(f, "{{")?;
     for rover in self.iter() {
-      writeln!(f, "  {}: {} {} {}", rover.id, rover.x, rover.y, rover.facing)?;
+      match &rover.name {
+        Some(name) => writeln!(f, "  {} ({}): {} {} {}", rover.id, name, rover.x, rover.y, rover.facing)?,
+        None => writeln!(f, "  {}: {} {} {}", rover.id, rover.x, rover.y, rover.facing)?,
+      }
     }
     write!(f, "}}")
   }
 }
+
+impl fmt::Display for MoveReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}{}", self.rover.label(), self.rover.pose(), self.trip)
+  }
+}
+
+impl fmt::Display for Trip {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for blocked in &self.blocked {
+      match blocked.slope {
+        Some(slope) => write!(f, " (step {} blocked at {} {}: slope {})", blocked.step, blocked.x, blocked.y, slope)?,
+        None => write!(f, " (step {} blocked at {} {})", blocked.step, blocked.x, blocked.y)?,
+      }
+    }
+    if let Some(step) = self.depleted {
+      write!(f, " (depleted on step {})", step)?;
+    }
+    Ok(())
+  }
+}
@@ -1,3 +1,21 @@
+table! {
+    use diesel::sql_types::*;
+    use crate::direction::*;
+
+    history (id) {
+        id -> Integer,
+        plateau_id -> Text,
+        rover_id -> Text,
+        batch -> Text,
+        created_at -> Timestamp,
+        command -> Text,
+        x -> Integer,
+        y -> Integer,
+        facing -> DirectionMapping,
+        poses -> Text,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
 
@@ -6,6 +24,13 @@ table! {
         created_at -> Timestamp,
         x_max -> Integer,
         y_max -> Integer,
+        name -> Nullable<Text>,
+        tags -> Text,
+        metadata -> Nullable<Text>,
+        charging_stations -> Text,
+        terrain -> Text,
+        heightmap -> Nullable<Text>,
+        georeference -> Nullable<Text>,
     }
 }
 
@@ -20,9 +45,18 @@ table! {
         y -> Integer,
         facing -> DirectionMapping,
         plateau_id -> Text,
+        name -> Nullable<Text>,
+        tags -> Text,
+        metadata -> Nullable<Text>,
+        battery_level -> Nullable<Integer>,
+        battery_capacity -> Nullable<Integer>,
+        turn_cost -> Nullable<Integer>,
+        move_cost -> Nullable<Integer>,
+        max_slope -> Nullable<Integer>,
     }
 }
 
+joinable!(history -> plateaus (plateau_id));
 joinable!(rovers -> plateaus (plateau_id));
 
-allow_tables_to_appear_in_same_query!(plateaus, rovers,);
+allow_tables_to_appear_in_same_query!(history, plateaus, rovers,);
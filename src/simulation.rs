@@ -0,0 +1,430 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Direction, Error, Motion, Plateau, Pose, Rover};
+
+/// What happens to a motion a rover can't carry out because its way is blocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockedPolicy {
+  /// The motion is dropped and the rover goes on with the rest of its path.
+  Skip,
+  /// The rover waits and tries the motion again on the next tick. A deadlock stops the simulation, a rover
+  /// blocked by a stationary obstacle stops there.
+  Wait,
+  /// Like `Wait`, but in a deadlock the rover added last gives up its blocked motion.
+  Yield,
+}
+
+/// Moves a set of rovers in lock step, every rover carrying out one motion per tick.
+///
+/// Turns always succeed. Moves are resolved against the positions at the start of the tick:
+/// * a rover may follow another rover into the cell it leaves during the same tick,
+/// * when several rovers move into the same cell, the rover added first wins,
+/// * two rovers swapping cells, i.e. driving head-on into each other, are both blocked,
+/// * a rover moving into a cell that stays occupied, by a rover or an obstacle, is blocked,
+/// * a rover the ground keeps from moving, an impassable cell or a steep slope, is blocked.
+///
+/// The [`BlockedPolicy`] decides what happens to the motion of a blocked rover.
+/// A rover whose battery can't pay for its next motion stops for good.
+/// Waiting rovers are checked every tick, so a simulation always ends: rovers waiting for each other in a cycle are
+/// a deadlock, rovers waiting for an obstacle or for a rover that doesn't move any more are stuck and stop for good.
+#[derive(Debug)]
+pub struct Simulation {
+  plateau: Plateau,
+  obstacles: HashSet<(i32, i32)>,
+  policy: BlockedPolicy,
+  rovers: Vec<Rover>,
+  paths: Vec<VecDeque<Motion>>,
+  tick: usize,
+  deadlock: Option<Deadlock>,
+}
+
+/// The state of one rover at the end of a tick.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RoverTick {
+  pub rover_id: String,
+  pub x: i32,
+  pub y: i32,
+  pub facing: Direction,
+  pub motion: Option<Motion>,
+  pub blocked: bool,
+  /// Whether the blocked motion is tried again on the next tick.
+  #[serde(default)]
+  pub waiting: bool,
+  #[serde(default)]
+  pub depleted: bool,
+  /// Whether the rover is blocked by a stationary obstacle for good and stopped.
+  #[serde(default)]
+  pub stuck: bool,
+  /// The traversal cost of the motion carried out.
+  #[serde(default)]
+  pub cost: i32,
+}
+
+/// The state of all the rovers at the end of a tick.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tick {
+  pub tick: usize,
+  pub rovers: Vec<RoverTick>,
+  /// The rovers that got stuck on this tick.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub stuck: Vec<WaitingRover>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub deadlock: Option<Deadlock>,
+}
+
+/// A rover that can't move on and the cell it is waiting for.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WaitingRover {
+  pub rover_id: String,
+  pub name: Option<String>,
+  pub x: i32,
+  pub y: i32,
+  pub target_x: i32,
+  pub target_y: i32,
+}
+
+/// Waiting rovers that will never move on because they wait for each other in a cycle.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Deadlock {
+  pub tick: usize,
+  pub rovers: Vec<WaitingRover>,
+  /// The rover that gave up its blocked motion to resolve the deadlock, if any.
+  pub yielded: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SimulationReport {
+  pub rovers: Vec<Rover>,
+  pub ticks: Vec<Tick>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub deadlock: Option<Deadlock>,
+}
+
+/// What a blocked rover waits for.
+enum Blocker {
+  Obstacle,
+  Rover(usize),
+}
+
+/// Where following blocked rovers to what they wait for ends.
+enum Chain {
+  /// The rovers wait for each other in a cycle.
+  Cycle(Vec<usize>),
+  /// The rovers wait, one behind the other, for an obstacle or for a rover that doesn't move any more.
+  Stationary(Vec<usize>),
+  /// The rovers wait for a rover that is moving.
+  Moving,
+}
+
+impl Simulation {
+  /// Creates a simulation on the plateau, the obstacles being cells no rover can move into,
+  /// e.g. the cells of the rovers not taking part.
+  pub fn new(plateau: Plateau, obstacles: HashSet<(i32, i32)>, policy: BlockedPolicy) -> Self {
+    Self {
+      plateau,
+      obstacles,
+      policy,
+      rovers: Vec::new(),
+      paths: Vec::new(),
+      tick: 0,
+      deadlock: None,
+    }
+  }
+
+  /// Adds a rover to the simulation. Rovers added first have priority over the ones added later.
+  /// Adding a rover again appends the motions to its path.
+  pub fn add_rover(&mut self, rover: Rover, motion_vector: Vec<Motion>) {
+    match self.rovers.iter().position(|other| other.id() == rover.id()) {
+      Some(n) => self.paths[n].extend(motion_vector),
+      None => {
+        self.rovers.push(rover);
+        self.paths.push(motion_vector.into());
+      }
+    }
+  }
+
+  pub fn rovers(&self) -> &[Rover] {
+    &self.rovers
+  }
+
+  pub fn into_rovers(self) -> Vec<Rover> {
+    self.rovers
+  }
+
+  /// The deadlock that stopped the simulation, with the `Wait` policy.
+  pub fn deadlock(&self) -> Option<&Deadlock> {
+    self.deadlock.as_ref()
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.deadlock.is_some() || self.paths.iter().all(|path| path.is_empty())
+  }
+
+  /// Advances every rover by one motion.
+  pub fn step(&mut self) -> Tick {
+    self.tick += 1;
+    let motions = self.paths.iter_mut().map(|path| path.pop_front()).collect::<Vec<Option<Motion>>>();
+    let cells = self.rovers.iter().map(|rover| (rover.x(), rover.y())).collect::<Vec<(i32, i32)>>();
+
+    let mut depleted = HashSet::new();
+    for (n, motion) in motions.iter().enumerate() {
+      if let Some(motion) = motion {
+        if !self.rovers[n].can_afford(motion, &self.plateau) {
+          depleted.insert(n);
+          self.paths[n].clear();
+        }
+      }
+    }
+
+    // The cells the rovers moving straight head for, the plateau bounds permitting.
+    // The rovers the ground keeps from moving are blocked from the start.
+    let mut targets = HashMap::new();
+    let mut grounded = HashSet::new();
+    for (n, motion) in motions.iter().enumerate() {
+      if depleted.contains(&n) {
+        continue;
+      }
+      if let Some(Motion::MoveStraight) = motion {
+        let ahead = self.rovers[n].cell_ahead(&self.plateau);
+        if ahead != cells[n] {
+          targets.insert(n, ahead);
+          if self.rovers[n].ground_stop(&self.plateau).is_some() {
+            grounded.insert(n);
+          }
+        }
+      }
+    }
+    let mut blocked = grounded.clone();
+
+    // The rover added first wins a cell several rovers head for.
+    let mut claims: HashMap<(i32, i32), usize> = HashMap::new();
+    for n in 0..self.rovers.len() {
+      if grounded.contains(&n) {
+        continue;
+      }
+      if let Some(target) = targets.get(&n) {
+        if claims.contains_key(target) {
+          blocked.insert(n);
+        } else {
+          claims.insert(*target, n);
+        }
+      }
+    }
+
+    // Rovers swapping cells would drive through each other.
+    for (n, target) in targets.iter() {
+      if let Some(other) = cells.iter().position(|cell| cell == target) {
+        if targets.get(&other) == Some(&cells[*n]) {
+          blocked.insert(*n);
+          blocked.insert(other);
+        }
+      }
+    }
+
+    // A rover can only move into a cell that is free or being left, until nothing changes.
+    loop {
+      let leaving = targets
+        .keys()
+        .filter(|n| !blocked.contains(*n))
+        .map(|n| cells[*n])
+        .collect::<HashSet<(i32, i32)>>();
+      let newly_blocked = targets
+        .iter()
+        .filter(|(n, target)| !blocked.contains(*n) && (self.obstacles.contains(*target) || (cells.contains(*target) && !leaving.contains(*target))))
+        .map(|(n, _)| *n)
+        .collect::<Vec<usize>>();
+      if newly_blocked.is_empty() {
+        break;
+      }
+      blocked.extend(newly_blocked);
+    }
+
+    let mut deadlock = None;
+    let mut waiting = HashSet::new();
+    let mut stuck = Vec::new();
+    if self.policy != BlockedPolicy::Skip {
+      waiting = blocked.clone();
+      let blocker = |n: &usize| match cells.iter().position(|cell| cell == &targets[n]) {
+        _ if self.obstacles.contains(&targets[n]) || grounded.contains(n) => Blocker::Obstacle,
+        Some(other) => Blocker::Rover(other),
+        None => Blocker::Rover(claims[&targets[n]]),
+      };
+      let stationary = |n: usize| depleted.contains(&n) || (motions[n].is_none() && self.paths[n].is_empty());
+      let waiting_rover = |n: &usize| WaitingRover {
+        rover_id: self.rovers[*n].id().to_string(),
+        name: self.rovers[*n].name().map(|name| name.to_string()),
+        x: cells[*n].0,
+        y: cells[*n].1,
+        target_x: targets[n].0,
+        target_y: targets[n].1,
+      };
+      let mut members = blocked.iter().copied().collect::<Vec<usize>>();
+      members.sort_unstable();
+      let mut stopped = Vec::new();
+      for n in members {
+        match self.follow_blocked(n, &blocked, stationary, blocker) {
+          Chain::Stationary(chain) => stopped.extend(chain),
+          Chain::Cycle(cycle) if deadlock.is_none() => {
+            deadlock = Some(Deadlock {
+              tick: self.tick,
+              rovers: cycle.iter().map(waiting_rover).collect(),
+              yielded: None,
+            })
+          }
+          _ => (),
+        }
+      }
+      stopped.sort_unstable();
+      stopped.dedup();
+      stuck = stopped.iter().map(waiting_rover).collect();
+      // A stuck rover stops for good, rather than waiting for a cell that never frees up.
+      for n in stopped {
+        waiting.remove(&n);
+        self.paths[n].clear();
+      }
+      if let Some(deadlock) = &mut deadlock {
+        match self.policy {
+          BlockedPolicy::Yield => {
+            let n = self
+              .rovers
+              .iter()
+              .rposition(|rover| deadlock.rovers.iter().any(|r| r.rover_id == rover.id()))
+              .unwrap();
+            deadlock.yielded = Some(self.rovers[n].id().to_string());
+            waiting.remove(&n);
+          }
+          _ => self.deadlock = Some(deadlock.clone()),
+        }
+      }
+    }
+
+    let mut rovers = Vec::new();
+    for (n, motion) in motions.into_iter().enumerate() {
+      let rover = &mut self.rovers[n];
+      let mut cost = 0;
+      if let Some(motion) = &motion {
+        if !blocked.contains(&n) && !depleted.contains(&n) {
+          cost = rover.apply_motion(motion.clone(), &self.plateau).unwrap_or_default();
+        } else if waiting.contains(&n) {
+          self.paths[n].push_front(motion.clone());
+        }
+      }
+      rovers.push(RoverTick {
+        rover_id: rover.id().to_string(),
+        x: rover.x(),
+        y: rover.y(),
+        facing: rover.facing(),
+        motion,
+        blocked: blocked.contains(&n),
+        waiting: waiting.contains(&n),
+        depleted: depleted.contains(&n),
+        stuck: stuck.iter().any(|stuck| stuck.rover_id == rover.id()),
+        cost,
+      });
+    }
+    Tick {
+      tick: self.tick,
+      rovers,
+      stuck,
+      deadlock,
+    }
+  }
+
+  /// Follows a blocked rover to what it waits for, through the blocked rovers it waits behind.
+  fn follow_blocked(&self, n: usize, blocked: &HashSet<usize>, stationary: impl Fn(usize) -> bool, blocker: impl Fn(&usize) -> Blocker) -> Chain {
+    let mut chain = vec![n];
+    loop {
+      match blocker(chain.last().unwrap()) {
+        Blocker::Obstacle => return Chain::Stationary(chain),
+        Blocker::Rover(other) if chain.contains(&other) => {
+          let start = chain.iter().position(|m| *m == other).unwrap();
+          return Chain::Cycle(chain.split_off(start));
+        }
+        Blocker::Rover(other) if blocked.contains(&other) => chain.push(other),
+        Blocker::Rover(other) if stationary(other) => return Chain::Stationary(chain),
+        Blocker::Rover(_) => return Chain::Moving,
+      }
+    }
+  }
+
+  /// Runs the simulation until every rover has gone through its path or a deadlock stops it.
+  pub fn run(&mut self) -> Vec<Tick> {
+    let mut ticks = Vec::new();
+    while !self.is_finished() {
+      ticks.push(self.step());
+    }
+    ticks
+  }
+}
+
+impl FromStr for BlockedPolicy {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "skip" => Ok(Self::Skip),
+      "wait" => Ok(Self::Wait),
+      "yield" => Ok(Self::Yield),
+      other => Err(Error::PolicyParseError(format!("Expected skip, wait or yield but found '{}'", other))),
+    }
+  }
+}
+
+impl fmt::Display for Tick {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Tick {}:", self.tick)?;
+    for (n, rover) in self.rovers.iter().enumerate() {
+      let separator = if n == 0 { " " } else { ", " };
+      write!(f, "{}{}", separator, Pose::new(rover.x, rover.y, rover.facing.clone()))?;
+      match (rover.blocked, rover.waiting, rover.stuck, rover.depleted) {
+        (true, true, _, _) => write!(f, " (waiting)")?,
+        (true, false, true, _) => write!(f, " (stuck)")?,
+        (true, false, false, _) => write!(f, " (blocked)")?,
+        (_, _, _, true) => write!(f, " (depleted)")?,
+        _ => (),
+      }
+    }
+    for stuck in self.stuck.iter() {
+      let label = stuck.name.as_deref().unwrap_or(&stuck.rover_id);
+      write!(
+        f,
+        " [{} at {} {} is blocked by a stationary obstacle at {} {}]",
+        label, stuck.x, stuck.y, stuck.target_x, stuck.target_y
+      )?;
+    }
+    if let Some(deadlock) = &self.deadlock {
+      write!(f, " [{}]", deadlock)?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for Deadlock {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "deadlock on tick {}:", self.tick)?;
+    for (n, rover) in self.rovers.iter().enumerate() {
+      let separator = if n == 0 { " " } else { ", " };
+      let label = rover.name.as_deref().unwrap_or(&rover.rover_id);
+      write!(
+        f,
+        "{}{} at {} {} waits for {} {}",
+        separator, label, rover.x, rover.y, rover.target_x, rover.target_y
+      )?;
+    }
+    if let Some(yielded) = &self.yielded {
+      let label = self
+        .rovers
+        .iter()
+        .find(|rover| &rover.rover_id == yielded)
+        .and_then(|rover| rover.name.as_deref())
+        .unwrap_or(yielded);
+      write!(f, "; {} gives up its move", label)?;
+    }
+    Ok(())
+  }
+}
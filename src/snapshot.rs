@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::history::HistoryEntry;
+use crate::store::Store;
+use crate::{labels, plateau, Error, Plateau, Rover};
+
+/// The version of the snapshot format. Snapshots of a later version are refused.
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// Every plateau of a store with its rovers and their history, as JSON.
+///
+/// Unlike a backup of the database, a snapshot doesn't depend on the schema: fields missing from the snapshot of an
+/// earlier build get their defaults, fields this build doesn't know are skipped. It can also be taken from SQLite and
+/// imported into PostgreSQL, or the other way around.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+  pub version: u64,
+  pub created_at: DateTime<Utc>,
+  pub plateaus: Vec<PlateauSnapshot>,
+}
+
+/// A plateau with its rovers, oldest first, and the commands they carried out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlateauSnapshot {
+  #[serde(flatten)]
+  pub plateau: Plateau,
+  #[serde(default)]
+  pub rovers: Vec<Rover>,
+  #[serde(default)]
+  pub history: Vec<HistoryEntry>,
+}
+
+impl Snapshot {
+  /// Takes every plateau of a store, oldest first.
+  pub fn take(store: &dyn Store) -> Result<Self, Error> {
+    let mut plateaus = Vec::new();
+    for plateau in store.list_plateaus(i64::MAX)?.iter().rev() {
+      let plateau_id = Uuid::from_str(plateau.id()).unwrap();
+      let mut rovers = store.list_rovers(plateau_id, i64::MAX)?.to_vec();
+      rovers.reverse();
+      plateaus.push(PlateauSnapshot {
+        plateau: plateau.clone(),
+        rovers,
+        history: store.list_history(plateau_id)?,
+      });
+    }
+    Ok(Self {
+      version: SNAPSHOT_VERSION,
+      created_at: Utc::now(),
+      plateaus,
+    })
+  }
+
+  /// Puts every plateau of the snapshot in a store, replacing the plateaus with the same ids, and gives back how many
+  /// were put. The snapshot is checked as a whole first, nothing is put unless it is sound. Each plateau is then put
+  /// with its rovers and history or not at all, the ones before a failure are kept.
+  pub fn import(self, store: &dyn Store) -> Result<usize, Error> {
+    self.check(store)?;
+    let total_plateaus = self.plateaus.len();
+    for snapshot in self.plateaus {
+      store.import_plateau(snapshot.plateau, snapshot.rovers, snapshot.history)?;
+    }
+    Ok(total_plateaus)
+  }
+}
+
+impl Snapshot {
+  /// Checks every plateau of the snapshot, and that its rovers aren't the rovers of another plateau, either of the
+  /// snapshot or of a plateau of the store that the snapshot doesn't replace.
+  fn check(&self, store: &dyn Store) -> Result<(), Error> {
+    let mut rover_ids = HashSet::new();
+    for snapshot in &self.plateaus {
+      snapshot
+        .check()
+        .map_err(|error| Error::SnapshotParseError(format!("Plateau {}: {}", snapshot.plateau.label(), error)))?;
+      if let Some(rover) = snapshot.rovers.iter().find(|rover| !rover_ids.insert(rover.id())) {
+        return Err(Error::SnapshotParseError(format!("Rover {} appears more than once", rover.id())));
+      }
+    }
+    let replaced = self.plateaus.iter().map(|snapshot| snapshot.plateau.id()).collect::<HashSet<&str>>();
+    for plateau in store.list_plateaus(i64::MAX)?.iter().filter(|plateau| !replaced.contains(plateau.id())) {
+      let rovers = store.list_rovers(Uuid::from_str(plateau.id()).unwrap(), i64::MAX)?;
+      if let Some(rover) = rovers.iter().find(|rover| rover_ids.contains(rover.id())) {
+        return Err(Error::SnapshotParseError(format!(
+          "Rover {} already belongs to plateau {}",
+          rover.id(),
+          plateau.label()
+        )));
+      }
+    }
+    Ok(())
+  }
+}
+
+impl PlateauSnapshot {
+  /// Checks the plateau as it is checked when created, that its rovers stand on passable cells of it, apart from each
+  /// other, with valid names and batteries, and that its history is the one of its rovers.
+  fn check(&self) -> Result<(), Error> {
+    let plateau = &self.plateau;
+    Plateau::check_bounds(plateau.x_max(), plateau.y_max())?;
+    let mut checked = plateau.clone();
+    checked.set_charging_stations(plateau.charging_stations().clone())?;
+    checked.set_terrain(plateau.terrain().clone())?;
+    checked.set_heightmap(plateau.heightmap().cloned())?;
+    let mut names = HashSet::new();
+    for (n, rover) in self.rovers.iter().enumerate() {
+      plateau::check_placement(plateau, &self.rovers[..n], rover)?;
+      if let Some(battery) = rover.battery() {
+        battery.to_request().to_battery()?;
+      }
+      if let Some(name) = rover.name() {
+        labels::validate_name(name)?;
+        if !names.insert(name) {
+          return Err(Error::NameTaken(name.into()));
+        }
+      }
+    }
+    let rover_ids = self.rovers.iter().map(|rover| rover.id()).collect::<HashSet<&str>>();
+    match self.history.iter().find(|entry| !rover_ids.contains(entry.rover_id.as_str())) {
+      Some(entry) => Err(Error::RoverNotFound(entry.rover_id.clone())),
+      None => Ok(()),
+    }
+  }
+}
+
+impl FromStr for Snapshot {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let snapshot: Value = serde_json::from_str(s).map_err(|error| Error::SnapshotParseError(error.to_string()))?;
+    // The version is checked first, a later format could be anything.
+    match snapshot.get("version").and_then(Value::as_u64) {
+      Some(version) if version > SNAPSHOT_VERSION => Err(Error::SnapshotParseError(format!(
+        "The snapshot is at version {}, later than version {} of this build",
+        version, SNAPSHOT_VERSION
+      ))),
+      Some(_) => serde_json::from_value(snapshot).map_err(|error| Error::SnapshotParseError(error.to_string())),
+      None => Err(Error::SnapshotParseError("The version of the snapshot is missing".into())),
+    }
+  }
+}
+
+impl fmt::Display for Snapshot {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let rovers: usize = self.plateaus.iter().map(|snapshot| snapshot.rovers.len()).sum();
+    let history: usize = self.plateaus.iter().map(|snapshot| snapshot.history.len()).sum();
+    write!(f, "{} plateaus, {} rovers and {} history entries", self.plateaus.len(), rovers, history)
+  }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use diesel::r2d2::ConnectionManager;
+use diesel::result::DatabaseErrorKind;
+use uuid::Uuid;
+
+use crate::geo::{self, ExportFormat};
+use crate::history::{self, HistoryEntry};
+use crate::memory::MemoryStore;
+use crate::plateau::{self, Plateaus};
+use crate::rover::{MoveReport, Rovers};
+use crate::simulation::{BlockedPolicy, SimulationReport};
+use crate::CONNECTION_POOL_ERROR;
+use crate::{render, ConnectionOptions, DBConnection, DBPool, Error, Georeference, Heightmap, Motion, Plateau, PlateauMap, Rover, TerrainMap};
+
+/// The `DATABASE_URL` that keeps the plateaus and rovers in memory instead of a database.
+pub const MEMORY_URL: &str = ":memory:";
+
+/// Where the plateaus, their rovers and the history of their commands are kept.
+///
+/// The backends only keep things, the motions are worked out the same way for all of them, see [`plateau::move_single`],
+/// [`plateau::move_tagged`], [`plateau::move_in_turn`] and [`plateau::simulate`]. A missing plateau or rover is a [`Error::PlateauNotFound`] or an [`Error::RoverNotFound`],
+/// a name already in use an [`Error::NameTaken`].
+pub trait Store: Send + Sync {
+  fn get_plateau(&self, plateau_id: Uuid) -> Result<Plateau, Error>;
+
+  fn get_plateau_by_name(&self, name: &str) -> Result<Plateau, Error>;
+
+  /// Looks up a plateau by either its id or its name.
+  fn find_plateau(&self, key: &str) -> Result<Plateau, Error> {
+    match Uuid::from_str(key) {
+      Ok(plateau_id) => self.get_plateau(plateau_id),
+      Err(_) => self.get_plateau_by_name(key),
+    }
+  }
+
+  /// The latest plateaus first.
+  fn list_plateaus(&self, total_plateaus: i64) -> Result<Plateaus, Error>;
+
+  /// Creates a plateau, giving it back with the id it is kept under.
+  fn create_plateau(&self, plateau: Plateau) -> Result<Plateau, Error>;
+
+  /// Creates the plateau of a map along with its rovers. Nothing is created if any of them can't be.
+  fn create_map(&self, map: PlateauMap) -> Result<PlateauMap, Error>;
+
+  /// Deletes a plateau along with its rovers and their history, giving back the number of rovers deleted.
+  fn delete_plateau(&self, plateau_id: Uuid) -> Result<usize, Error>;
+
+  /// Puts back a plateau with its rovers and their history as they were, keeping their ids and dates, e.g. from a
+  /// snapshot. A plateau with the same id is replaced. Nothing is put back if any of them can't be.
+  fn import_plateau(&self, plateau: Plateau, rovers: Vec<Rover>, history: Vec<HistoryEntry>) -> Result<(), Error>;
+
+  /// Replaces the terrain layer of a plateau.
+  fn set_plateau_terrain(&self, plateau_id: Uuid, terrain: TerrainMap) -> Result<Plateau, Error>;
+
+  /// Replaces the heightmap of a plateau.
+  fn set_plateau_heightmap(&self, plateau_id: Uuid, heightmap: Option<Heightmap>) -> Result<Plateau, Error>;
+
+  /// Replaces the georeference of a plateau.
+  fn set_plateau_georeference(&self, plateau_id: Uuid, georeference: Option<Georeference>) -> Result<Plateau, Error>;
+
+  /// The commands carried out on a plateau, oldest first.
+  fn list_history(&self, plateau_id: Uuid) -> Result<Vec<HistoryEntry>, Error>;
+
+  fn get_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<Rover, Error>;
+
+  fn get_rover_by_name(&self, plateau_id: Uuid, name: &str) -> Result<Rover, Error>;
+
+  /// Looks up a rover of the given plateau by either its id or its name.
+  fn find_rover(&self, plateau_id: Uuid, key: &str) -> Result<Rover, Error> {
+    match Uuid::from_str(key) {
+      Ok(rover_id) => self.get_rover(plateau_id, rover_id),
+      Err(_) => self.get_rover_by_name(plateau_id, key),
+    }
+  }
+
+  /// The rover created `n`-th from the latest.
+  fn get_rover_n(&self, n: i64, plateau_id: Uuid) -> Result<Rover, Error>;
+
+  fn get_rovers_count(&self, plateau_id: Uuid) -> Result<usize, Error>;
+
+  /// The latest rovers of a plateau first.
+  fn list_rovers(&self, plateau_id: Uuid, total_rovers: i64) -> Result<Rovers, Error>;
+
+  /// Creates a rover, giving it back with the id it is kept under.
+  fn create_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error>;
+
+  /// Creates a rover after checking that its cell is on the plateau, passable and free of other rovers.
+  fn place_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let plateau = self.get_plateau(plateau_id)?;
+    plateau::check_placement(&plateau, &self.list_rovers(plateau_id, i64::MAX)?, &rover)?;
+    self.create_rover(plateau_id, rover)
+  }
+
+  /// Puts a rover of the given plateau back in an earlier state, e.g. to undo a command. The jump is recorded in the
+  /// history of the rover as an `undo` command.
+  fn restore_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error>;
+
+  /// Deletes a rover of the given plateau along with its history.
+  fn delete_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<(), Error>;
+
+  /// Moves a rover along a motion path, see [`plateau::move_single`]. Several rovers are moved in lock step by
+  /// [`Store::simulate_moves`].
+  fn move_rover(&self, plateau_id: Uuid, rover_id: Uuid, motion_vector: Vec<Motion>) -> Result<MoveReport, Error>;
+
+  /// Moves every rover of the plateau tagged with `tag` along the same path, see [`plateau::move_tagged`].
+  fn move_group(&self, plateau_id: Uuid, tag: &str, motion_vector: Vec<Motion>) -> Result<Vec<MoveReport>, Error>;
+
+  /// Moves the rovers one after the other, each along its whole path, see [`plateau::move_in_turn`].
+  /// Nothing is kept if any rover can't be found or would collide with another rover.
+  fn move_rovers(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>) -> Result<Vec<Rover>, Error>;
+
+  /// Moves the rovers in lock step, one motion per tick, see [`plateau::simulate`].
+  /// Nothing is kept if a deadlock stops the rovers, the report then holds the deadlock.
+  fn simulate_moves(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>, policy: BlockedPolicy) -> Result<SimulationReport, Error>;
+
+  /// Draws a plateau with its rovers as an SVG image, see [`render::svg`].
+  /// The paths are previewed: the trajectories the rovers would follow along them are drawn, nothing is moved.
+  fn render_plateau(&self, plateau_id: Uuid, paths: Vec<(String, Vec<Motion>)>) -> Result<String, Error> {
+    let plateau = self.get_plateau(plateau_id)?;
+    let mut rovers = self.list_rovers(plateau_id, i64::MAX)?.to_vec();
+    let mut trajectories = Vec::new();
+    for (rover_key, motion_vector) in paths {
+      let n = match rovers.iter().position(|rover| rover.id() == rover_key || rover.name() == Some(&rover_key)) {
+        Some(n) => n,
+        None => return Err(Error::RoverNotFound(rover_key)),
+      };
+      let occupied = rovers
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != n)
+        .map(|(_, rover)| (rover.x(), rover.y()))
+        .collect::<HashSet<(i32, i32)>>();
+      trajectories.push(rovers[n].apply_motion_vector_avoiding(motion_vector, &plateau, &occupied).trajectory);
+    }
+
+    render::svg(&plateau, &rovers, &trajectories)
+  }
+
+  /// Exports a georeferenced plateau with its obstacles, its rovers and the trajectories they went through in its history.
+  fn export_plateau(&self, plateau_id: Uuid, format: ExportFormat) -> Result<String, Error> {
+    let plateau = self.get_plateau(plateau_id)?;
+    let rovers = self.list_rovers(plateau_id, i64::MAX)?.to_vec();
+    let entries = self.list_history(plateau_id)?;
+
+    geo::export(format, &plateau, &rovers, &history::trajectories(&entries, &rovers))
+  }
+
+  /// Replays the command history of a plateau, one frame per step of its rovers.
+  fn replay_plateau(&self, plateau_id: Uuid) -> Result<(Plateau, Vec<Vec<Rover>>), Error> {
+    let plateau = self.get_plateau(plateau_id)?;
+    let rovers = self.list_rovers(plateau_id, i64::MAX)?.to_vec();
+    let entries = self.list_history(plateau_id)?;
+
+    Ok((plateau, history::frames(&entries, &rovers)))
+  }
+}
+
+/// Opens the store a `DATABASE_URL` names: [`MEMORY_URL`] keeps everything in memory, anything else is a database,
+/// migrated to the schema of this build.
+pub fn open(database_url: &str) -> Result<Arc<dyn Store>, Error> {
+  match database_url {
+    MEMORY_URL => Ok(Arc::new(MemoryStore::new())),
+    _ => {
+      let database = DatabaseStore::open(database_url)?;
+      database.migrate()?;
+      Ok(Arc::new(database))
+    }
+  }
+}
+
+/// Whether a `DATABASE_URL` names a PostgreSQL database rather than a SQLite file.
+pub fn is_postgres_url(database_url: &str) -> bool {
+  database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+}
+
+/// The plateaus, rovers and history kept in the database, through the functions of [`crate::plateau`]. The database
+/// is SQLite, or PostgreSQL when built with the `postgres` feature.
+#[derive(Clone)]
+pub struct DatabaseStore {
+  pool: DBPool,
+}
+
+/// Tells a missing plateau or rover apart from the other database errors.
+fn not_found(error: impl Into<Error>, missing: impl FnOnce() -> Error) -> Error {
+  match error.into() {
+    Error::DatabaseError(diesel::result::Error::NotFound) => missing(),
+    error => error,
+  }
+}
+
+/// Tells a name already in use apart from the other database errors.
+fn name_taken(error: diesel::result::Error, name: Option<String>) -> Error {
+  match (error, name) {
+    (diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _), Some(name)) => Error::NameTaken(name),
+    (error, _) => Error::DatabaseError(error),
+  }
+}
+
+impl DatabaseStore {
+  pub fn new(pool: DBPool) -> Self {
+    Self { pool }
+  }
+
+  /// Opens the database with a pool of connections in WAL mode, waiting up to 30 seconds for a locked database.
+  ///
+  /// Fails when `database_url` names a database of the other backend than the one built in, or the pool can't be built.
+  pub fn open(database_url: &str) -> Result<Self, Error> {
+    match (is_postgres_url(database_url), cfg!(feature = "postgres")) {
+      (true, false) => {
+        return Err(Error::ConnectionError(
+          "DATABASE_URL names a PostgreSQL database, build with the postgres feature to use it".into(),
+        ))
+      }
+      (false, true) => {
+        return Err(Error::ConnectionError(format!(
+          "DATABASE_URL must name a PostgreSQL database or be {}, this build keeps its data in PostgreSQL",
+          MEMORY_URL
+        )))
+      }
+      _ => (),
+    }
+    let manager = ConnectionManager::<DBConnection>::new(database_url);
+    let pool = r2d2::Pool::builder()
+      .connection_customizer(Box::new(ConnectionOptions {
+        enable_wal: true,
+        enable_foreign_keys: true,
+        busy_timeout: Some(Duration::from_secs(30)),
+      }))
+      .build(manager)
+      .map_err(|error| Error::ConnectionError(error.to_string()))?;
+    Ok(Self::new(pool))
+  }
+
+  /// Runs the migrations the database lacks, see [`crate::db::migrate`].
+  pub fn migrate(&self) -> Result<Vec<String>, Error> {
+    crate::db::migrate(&self.connection()?)
+  }
+
+  pub fn pool(&self) -> &DBPool {
+    &self.pool
+  }
+
+  /// A connection of the pool, which fails when none frees up in time.
+  pub fn connection(&self) -> Result<crate::DBPooledConnection, Error> {
+    self
+      .pool
+      .get()
+      .map_err(|error| Error::ConnectionError(format!("{}: {}", CONNECTION_POOL_ERROR, error)))
+  }
+}
+
+impl Store for DatabaseStore {
+  fn get_plateau(&self, plateau_id: Uuid) -> Result<Plateau, Error> {
+    plateau::get_plateau(plateau_id, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn get_plateau_by_name(&self, name: &str) -> Result<Plateau, Error> {
+    plateau::get_plateau_by_name(name, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(name.into())))
+  }
+
+  fn list_plateaus(&self, total_plateaus: i64) -> Result<Plateaus, Error> {
+    Ok(plateau::list_plateaus(total_plateaus, &self.connection()?)?)
+  }
+
+  fn create_plateau(&self, plateau: Plateau) -> Result<Plateau, Error> {
+    let name = plateau.name().map(String::from);
+    plateau::create_plateau(plateau, &self.connection()?).map_err(|error| name_taken(error, name))
+  }
+
+  fn create_map(&self, map: PlateauMap) -> Result<PlateauMap, Error> {
+    let name = map.plateau.name().map(String::from);
+    plateau::create_map(map, &self.connection()?).map_err(|error| name_taken(error, name))
+  }
+
+  fn import_plateau(&self, plateau: Plateau, rovers: Vec<Rover>, history: Vec<HistoryEntry>) -> Result<(), Error> {
+    let name = plateau.name().map(String::from);
+    plateau::import_plateau(plateau, rovers, history, &self.connection()?).map_err(|error| name_taken(error, name))
+  }
+
+  fn delete_plateau(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    plateau::delete_plateau(plateau_id, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn set_plateau_terrain(&self, plateau_id: Uuid, terrain: TerrainMap) -> Result<Plateau, Error> {
+    plateau::set_plateau_terrain(plateau_id, terrain, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn set_plateau_heightmap(&self, plateau_id: Uuid, heightmap: Option<Heightmap>) -> Result<Plateau, Error> {
+    plateau::set_plateau_heightmap(plateau_id, heightmap, &self.connection()?)
+      .map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn set_plateau_georeference(&self, plateau_id: Uuid, georeference: Option<Georeference>) -> Result<Plateau, Error> {
+    plateau::set_plateau_georeference(plateau_id, georeference, &self.connection()?)
+      .map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn list_history(&self, plateau_id: Uuid) -> Result<Vec<HistoryEntry>, Error> {
+    Ok(history::list_history(plateau_id, &self.connection()?)?)
+  }
+
+  fn get_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<Rover, Error> {
+    plateau::get_rover(plateau_id, rover_id, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(rover_id.to_string())))
+  }
+
+  fn get_rover_by_name(&self, plateau_id: Uuid, name: &str) -> Result<Rover, Error> {
+    plateau::get_rover_by_name(plateau_id, name, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(name.into())))
+  }
+
+  fn get_rover_n(&self, n: i64, plateau_id: Uuid) -> Result<Rover, Error> {
+    plateau::get_rover_n(n, plateau_id, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(format!("{}", n + 1))))
+  }
+
+  fn get_rovers_count(&self, plateau_id: Uuid) -> Result<usize, Error> {
+    Ok(plateau::get_rovers_count(plateau_id, &self.connection()?)?)
+  }
+
+  fn list_rovers(&self, plateau_id: Uuid, total_rovers: i64) -> Result<Rovers, Error> {
+    Ok(plateau::list_rovers(plateau_id, total_rovers, &self.connection()?)?)
+  }
+
+  fn create_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let name = rover.name().map(String::from);
+    plateau::create_rover(plateau_id, rover, &self.connection()?).map_err(|error| name_taken(error, name))
+  }
+
+  fn restore_rover(&self, plateau_id: Uuid, rover: Rover) -> Result<Rover, Error> {
+    let rover_id = rover.id().to_string();
+    plateau::restore_rover(plateau_id, rover, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(rover_id)))
+  }
+
+  fn delete_rover(&self, plateau_id: Uuid, rover_id: Uuid) -> Result<(), Error> {
+    plateau::delete_rover(plateau_id, rover_id, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(rover_id.to_string())))
+  }
+
+  fn move_rover(&self, plateau_id: Uuid, rover_id: Uuid, motion_vector: Vec<Motion>) -> Result<MoveReport, Error> {
+    plateau::move_rover(plateau_id, rover_id, motion_vector, &self.connection()?)
+      .map_err(|error| not_found(error, || Error::RoverNotFound(rover_id.to_string())))
+  }
+
+  fn move_group(&self, plateau_id: Uuid, tag: &str, motion_vector: Vec<Motion>) -> Result<Vec<MoveReport>, Error> {
+    plateau::move_group(plateau_id, tag, motion_vector, &self.connection()?).map_err(|error| not_found(error, || Error::RoverNotFound(tag.into())))
+  }
+
+  fn move_rovers(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>) -> Result<Vec<Rover>, Error> {
+    plateau::move_rovers(plateau_id, moves, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+
+  fn simulate_moves(&self, plateau_id: Uuid, moves: Vec<(String, Vec<Motion>)>, policy: BlockedPolicy) -> Result<SimulationReport, Error> {
+    plateau::simulate_moves(plateau_id, moves, policy, &self.connection()?).map_err(|error| not_found(error, || Error::PlateauNotFound(plateau_id.to_string())))
+  }
+}
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::plateau::Cells;
+use crate::Error;
+
+/// The ground of a cell, which decides what it takes a rover to move into it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Terrain {
+  #[default]
+  Rock,
+  Sand,
+  Ice,
+  Impassable,
+}
+
+impl Terrain {
+  /// The cost of moving into a cell of this terrain, `None` if no rover can move into it.
+  pub fn cost(&self) -> Option<i32> {
+    match self {
+      Terrain::Rock => Some(1),
+      Terrain::Sand => Some(2),
+      Terrain::Ice => Some(3),
+      Terrain::Impassable => None,
+    }
+  }
+
+  /// The character standing for the terrain in a terrain block.
+  pub fn symbol(&self) -> char {
+    match self {
+      Terrain::Rock => '.',
+      Terrain::Sand => 's',
+      Terrain::Ice => 'i',
+      Terrain::Impassable => '#',
+    }
+  }
+
+  pub fn from_symbol(symbol: char) -> Option<Self> {
+    match symbol {
+      '.' | 'r' | 'R' => Some(Terrain::Rock),
+      's' | 'S' => Some(Terrain::Sand),
+      'i' | 'I' => Some(Terrain::Ice),
+      '#' => Some(Terrain::Impassable),
+      _ => None,
+    }
+  }
+}
+
+impl FromStr for Terrain {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "rock" => Ok(Terrain::Rock),
+      "sand" => Ok(Terrain::Sand),
+      "ice" => Ok(Terrain::Ice),
+      "impassable" => Ok(Terrain::Impassable),
+      other => Err(Error::TerrainParseError(format!(
+        "Expected rock, sand, ice or impassable but found '{}'",
+        other
+      ))),
+    }
+  }
+}
+
+impl fmt::Display for Terrain {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      Terrain::Rock => "rock",
+      Terrain::Sand => "sand",
+      Terrain::Ice => "ice",
+      Terrain::Impassable => "impassable",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// The terrain of the cells of a plateau, the cells left out being rock.
+///
+/// It is written as the cells of every terrain, e.g. `{"sand": [[1, 2]], "impassable": [[3, 3]]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "BTreeMap<Terrain, Cells>", into = "BTreeMap<Terrain, Cells>")]
+pub struct TerrainMap(BTreeMap<(i32, i32), Terrain>);
+
+impl TerrainMap {
+  pub fn new() -> Self {
+    Self(BTreeMap::new())
+  }
+
+  /// Reads a terrain block, one row of symbols per line from the top row (`y_max`) down to row 0.
+  pub fn from_rows(rows: &[String], x_max: i32, y_max: i32) -> Result<Self, Error> {
+    if rows.len() as i32 != y_max + 1 {
+      return Err(Error::TerrainParseError(format!("Expected {} rows but found {}", y_max + 1, rows.len())));
+    }
+    let mut terrain = Self::new();
+    for (n, row) in rows.iter().enumerate() {
+      let y = y_max - n as i32;
+      let symbols = row.trim().chars().collect::<Vec<char>>();
+      if symbols.len() as i32 != x_max + 1 {
+        return Err(Error::TerrainParseError(format!(
+          "Expected {} cells on row {} but found {}",
+          x_max + 1,
+          y,
+          symbols.len()
+        )));
+      }
+      for (x, symbol) in symbols.into_iter().enumerate() {
+        match Terrain::from_symbol(symbol) {
+          Some(kind) => terrain.insert(x as i32, y, kind),
+          None => return Err(Error::TerrainParseError(format!("Unknown terrain '{}' at {} {}", symbol, x, y))),
+        }
+      }
+    }
+    Ok(terrain)
+  }
+
+  pub fn get(&self, x: i32, y: i32) -> Terrain {
+    self.0.get(&(x, y)).copied().unwrap_or_default()
+  }
+
+  pub fn insert(&mut self, x: i32, y: i32, terrain: Terrain) {
+    match terrain {
+      Terrain::Rock => self.0.remove(&(x, y)),
+      terrain => self.0.insert((x, y), terrain),
+    };
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// The cells that aren't rock.
+  pub fn cells(&self) -> Cells {
+    self.0.keys().copied().collect()
+  }
+
+  pub fn impassable_cells(&self) -> Cells {
+    self
+      .0
+      .iter()
+      .filter(|(_, terrain)| **terrain == Terrain::Impassable)
+      .map(|(cell, _)| *cell)
+      .collect()
+  }
+}
+
+impl From<BTreeMap<Terrain, Cells>> for TerrainMap {
+  fn from(cells: BTreeMap<Terrain, Cells>) -> Self {
+    let mut terrain = Self::new();
+    for (kind, cells) in cells {
+      for (x, y) in cells {
+        terrain.insert(x, y, kind);
+      }
+    }
+    terrain
+  }
+}
+
+impl From<TerrainMap> for BTreeMap<Terrain, Cells> {
+  fn from(terrain: TerrainMap) -> Self {
+    let mut cells: BTreeMap<Terrain, Cells> = BTreeMap::new();
+    for (cell, kind) in terrain.0 {
+      cells.entry(kind).or_default().insert(cell);
+    }
+    cells
+  }
+}
@@ -0,0 +1,191 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
+use ratatui::Frame;
+
+use crate::repl::{Reply, Session};
+use crate::store::Store;
+use crate::{render, Plateau, Rover};
+
+/// The lines of command output the dashboard keeps.
+const LOG_SIZE: usize = 100;
+
+/// The full-screen dashboard of `gcs_cli tui`: the grid of the selected plateau with its rovers, the list of the rovers
+/// with their statuses, and a command line taking the commands of the shell.
+#[derive(Debug, Default)]
+pub struct Dashboard {
+  session: Session,
+  rovers: Vec<Rover>,
+  input: String,
+  /// The commands run so far and what they printed, errors being marked.
+  log: Vec<(String, bool)>,
+  /// The commands run so far, the latest last, and the one shown on the command line while going through them.
+  history: Vec<String>,
+  recalled: Option<usize>,
+}
+
+/// What a rover is up to, as the rover list shows it.
+fn status(rover: &Rover, plateau: &Plateau) -> &'static str {
+  match rover.battery() {
+    Some(battery) if battery.is_depleted() && plateau.is_charging_station(rover.x(), rover.y()) => "charging",
+    Some(battery) if battery.is_depleted() => "depleted",
+    _ => "ready",
+  }
+}
+
+impl Dashboard {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Reads the selected plateau and its rovers again, selecting the latest plateau when none is.
+  pub fn refresh(&mut self, store: &dyn Store) {
+    self.session.refresh(store);
+    if self.session.plateau().is_none() {
+      if let Some(plateau) = store.list_plateaus(1).ok().and_then(|plateaus| plateaus.first().cloned()) {
+        self.session.select_plateau(plateau);
+      }
+    }
+    self.rovers = match self.session.plateau_id() {
+      Some(plateau_id) => store.list_rovers(plateau_id, i64::MAX).map(|rovers| rovers.to_vec()).unwrap_or_default(),
+      None => Vec::new(),
+    };
+  }
+
+  fn log(&mut self, text: &str, error: bool) {
+    self.log.extend(text.lines().map(|line| (line.to_string(), error)));
+    let excess = self.log.len().saturating_sub(LOG_SIZE);
+    self.log.drain(..excess);
+  }
+
+  fn run(&mut self, store: &dyn Store) -> bool {
+    let line = std::mem::take(&mut self.input);
+    self.recalled = None;
+    if line.trim().is_empty() {
+      return false;
+    }
+    self.log(&format!("> {}", line), false);
+    self.history.push(line.clone());
+    let quit = match self.session.execute(&line, store) {
+      Ok(Reply::Print(text)) => {
+        self.log(&text, false);
+        false
+      }
+      Ok(Reply::Quit) => true,
+      Err(error) => {
+        self.log(&error.to_string(), true);
+        false
+      }
+    };
+    self.refresh(store);
+    quit
+  }
+
+  fn recall(&mut self, recalled: Option<usize>) {
+    self.recalled = recalled;
+    self.input = recalled.map(|n| self.history[n].clone()).unwrap_or_default();
+  }
+
+  /// Edits or runs the command line, telling whether the dashboard is to be left.
+  pub fn handle_key(&mut self, key: KeyEvent, store: &dyn Store) -> bool {
+    match key.code {
+      KeyCode::Esc => return true,
+      KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+      KeyCode::Enter => return self.run(store),
+      KeyCode::Char(c) => self.input.push(c),
+      KeyCode::Backspace => {
+        self.input.pop();
+      }
+      KeyCode::Up if !self.history.is_empty() => self.recall(Some(self.recalled.unwrap_or(self.history.len()).saturating_sub(1))),
+      KeyCode::Down => self.recall(self.recalled.map(|n| n + 1).filter(|n| *n < self.history.len())),
+      _ => (),
+    }
+    false
+  }
+
+  pub fn draw(&self, frame: &mut Frame) {
+    let [top, log, command] = Layout::vertical([Constraint::Min(5), Constraint::Length(8), Constraint::Length(3)]).areas(frame.area());
+    let [grid, rovers] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+
+    match self.session.plateau() {
+      Some(plateau) => {
+        let title = format!(" Plateau {} ({}) ", plateau.label(), plateau);
+        frame.render_widget(
+          Paragraph::new(self.grid(plateau)).block(Block::default().borders(Borders::ALL).title(title)),
+          grid,
+        );
+        frame.render_widget(self.rover_table(plateau), rovers);
+      }
+      None => {
+        let text = "No plateau yet, type create plateau X_MAX Y_MAX";
+        frame.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(" Plateau ")), grid);
+        frame.render_widget(Block::default().borders(Borders::ALL).title(" Rovers "), rovers);
+      }
+    }
+
+    let shown = log.height.saturating_sub(2) as usize;
+    let lines = self.log[self.log.len().saturating_sub(shown)..]
+      .iter()
+      .map(|(line, error)| match error {
+        true => Line::styled(line.as_str(), Style::default().fg(Color::Red)),
+        false => Line::raw(line.as_str()),
+      })
+      .collect::<Vec<Line>>();
+    frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Output ")), log);
+
+    let title = " Command (help lists them, Esc leaves) ";
+    frame.render_widget(
+      Paragraph::new(format!("> {}", self.input)).block(Block::default().borders(Borders::ALL).title(title)),
+      command,
+    );
+    frame.set_cursor_position(Position::new(command.x + 3 + self.input.chars().count() as u16, command.y + 1));
+  }
+
+  /// The plateau as `render::ascii` draws it, the rovers in green and the selected one highlighted.
+  fn grid(&self, plateau: &Plateau) -> Vec<Line<'static>> {
+    let selected = self.session.rover().map(|rover| (rover.x(), rover.y()));
+    render::ascii(plateau, &self.rovers, &[])
+      .lines()
+      .zip((0..=plateau.y_max()).rev())
+      .map(|(row, y)| {
+        let spans = row.chars().enumerate().map(|(x, symbol)| {
+          let style = match symbol {
+            _ if selected == Some((x as i32, y)) => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            '^' | '>' | 'v' | '<' => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            '#' => Style::default().fg(Color::DarkGray),
+            '+' => Style::default().fg(Color::Cyan),
+            _ => Style::default(),
+          };
+          Span::styled(symbol.to_string(), style)
+        });
+        Line::from(spans.collect::<Vec<Span>>())
+      })
+      .collect()
+  }
+
+  fn rover_table(&self, plateau: &Plateau) -> Table<'_> {
+    let selected = self.session.rover().map(|rover| rover.id());
+    let rows = self.rovers.iter().map(|rover| {
+      let battery = rover
+        .battery()
+        .map(|battery| format!("{}/{}", battery.level(), battery.capacity()))
+        .unwrap_or_else(|| "-".into());
+      let row = Row::new(vec![
+        rover.label().to_string(),
+        rover.pose().to_string(),
+        battery,
+        status(rover, plateau).to_string(),
+      ]);
+      match Some(rover.id()) == selected {
+        true => row.style(Style::default().add_modifier(Modifier::REVERSED)),
+        false => row,
+      }
+    });
+    let widths = [Constraint::Min(10), Constraint::Length(7), Constraint::Length(7), Constraint::Length(8)];
+    Table::new(rows, widths)
+      .header(Row::new(vec!["Rover", "Pose", "Battery", "Status"]).style(Style::default().add_modifier(Modifier::BOLD)))
+      .block(Block::default().borders(Borders::ALL).title(format!(" Rovers ({}) ", self.rovers.len())))
+  }
+}
@@ -1,16 +1,17 @@
 use std::fs::read_to_string;
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Stdio};
 
 #[test]
 fn test_stdout() {
   let output = if cfg!(target_os = "windows") {
     Command::new("cargo")
-      .args(["run", "--release", "--", "--input", ".\\tests\\input.txt"])
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\input.txt"])
       .output()
       .expect("failed to execute process")
   } else {
     Command::new("cargo")
-      .args(["run", "--release", "--", "--input", "./tests/input.txt"])
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input.txt"])
       .output()
       .expect("failed to execute process")
   };
@@ -26,12 +27,30 @@ fn test_stdout() {
 fn test_output() {
   if cfg!(target_os = "windows") {
     Command::new("cargo")
-      .args(["run", "--release", "--", "--input", ".\\tests\\input.txt", "--output", "output.txt"])
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        ".\\tests\\input.txt",
+        "--output",
+        "output.txt",
+      ])
       .output()
       .expect("failed to execute process");
   } else {
     Command::new("cargo")
-      .args(["run", "--release", "--", "--input", "./tests/input.txt", "--output", "output.txt"])
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        "./tests/input.txt",
+        "--output",
+        "output.txt",
+      ])
       .output()
       .expect("failed to execute process");
   };
@@ -41,3 +60,871 @@ fn test_output() {
     assert_eq!(read_to_string("./output.txt").unwrap(), read_to_string("./tests/output.txt").unwrap());
   }
 }
+
+#[test]
+fn test_concurrent() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        ".\\tests\\input_concurrent.txt",
+        "--concurrent",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        "./tests/input_concurrent.txt",
+        "--concurrent",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_concurrent.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_concurrent.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_deadlock() {
+  for policy in ["skip", "wait", "yield"] {
+    let output = if cfg!(target_os = "windows") {
+      Command::new("cargo")
+        .args([
+          "run",
+          "--release",
+          "--",
+          "--ephemeral",
+          "--input",
+          ".\\tests\\mission_deadlock.yaml",
+          "--concurrent",
+          "--on-blocked",
+          policy,
+        ])
+        .output()
+        .expect("failed to execute process")
+    } else {
+      Command::new("cargo")
+        .args([
+          "run",
+          "--release",
+          "--",
+          "--ephemeral",
+          "--input",
+          "./tests/mission_deadlock.yaml",
+          "--concurrent",
+          "--on-blocked",
+          policy,
+        ])
+        .output()
+        .expect("failed to execute process")
+    };
+    // Only the rovers waiting for each other in the corridor are a deadlock.
+    let stderr = String::from_utf8(output.stderr).expect("Found invalid UTF-8");
+    assert_eq!(stderr.contains("deadlock on tick 3"), policy == "wait");
+    let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+    if cfg!(target_os = "windows") {
+      assert_eq!(stdout, read_to_string(format!(".\\tests\\output_deadlock_{}.txt", policy)).unwrap());
+    } else {
+      assert_eq!(stdout, read_to_string(format!("./tests/output_deadlock_{}.txt", policy)).unwrap());
+    }
+  }
+}
+
+#[test]
+fn test_battery() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        ".\\tests\\input_battery.txt",
+        "--battery",
+        "3",
+        "--charging-station",
+        "0,2",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        "./tests/input_battery.txt",
+        "--battery",
+        "3",
+        "--charging-station",
+        "0,2",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_battery.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_battery.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_negative_battery_cost() {
+  let output = Command::new("cargo")
+    .args([
+      "run",
+      "--release",
+      "--quiet",
+      "--",
+      "--ephemeral",
+      "--input",
+      "./tests/input_battery.txt",
+      "--battery",
+      "3",
+      "--move-cost=-1",
+    ])
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr).expect("Found invalid UTF-8").starts_with("Invalid battery: "));
+}
+
+#[test]
+fn test_terrain() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\input_terrain.txt"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input_terrain.txt"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_terrain.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_terrain.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_heightmap() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        ".\\tests\\input.txt",
+        "--heightmap",
+        ".\\tests\\heightmap.csv",
+        "--max-slope",
+        "2",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        "./tests/input.txt",
+        "--heightmap",
+        "./tests/heightmap.csv",
+        "--max-slope",
+        "2",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_heightmap.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_heightmap.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_heightmap_bound() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        ".\\tests\\input.txt",
+        "--heightmap",
+        ".\\tests\\heightmap.csv",
+        "--max-slope",
+        "1",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--input",
+        "./tests/input.txt",
+        "--heightmap",
+        "./tests/heightmap.csv",
+        "--max-slope",
+        "1",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  // A step as steep as the maximum slope is blocked.
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_heightmap_bound.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_heightmap_bound.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_map() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        ".\\tests\\map.txt",
+        "--input",
+        ".\\tests\\input_map.txt",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        "./tests/map.txt",
+        "--input",
+        "./tests/input_map.txt",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_map.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_map.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_render() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        ".\\tests\\map.txt",
+        "--input",
+        ".\\tests\\input_map.txt",
+        "--render",
+        "--trajectories",
+      ])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        "./tests/map.txt",
+        "--input",
+        "./tests/input_map.txt",
+        "--render",
+        "--trajectories",
+      ])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_render.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_render.txt").unwrap());
+  }
+}
+
+/// Drops the lines holding `marker`, i.e. the random ids of the plateaus and rovers.
+fn without_lines(text: &str, marker: &str) -> String {
+  text.lines().filter(|line| !line.contains(marker)).map(|line| format!("{}\n", line)).collect()
+}
+
+#[test]
+fn test_geojson() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        ".\\tests\\map.txt",
+        "--input",
+        ".\\tests\\input_map.txt",
+      ])
+      .args(["--georeference", "-33.8688,151.2093,10,30", "--export", "geojson"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        "./tests/map.txt",
+        "--input",
+        "./tests/input_map.txt",
+      ])
+      .args(["--georeference", "-33.8688,151.2093,10,30", "--export", "geojson"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = without_lines(&String::from_utf8(output.stdout).expect("Found invalid UTF-8"), "id\"");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_geojson.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_geojson.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_kml() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        ".\\tests\\map.txt",
+        "--input",
+        ".\\tests\\input_map.txt",
+      ])
+      .args(["--georeference", "48.8566,2.3522,10", "--export", "kml"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args([
+        "run",
+        "--release",
+        "--",
+        "--ephemeral",
+        "--map",
+        "./tests/map.txt",
+        "--input",
+        "./tests/input_map.txt",
+      ])
+      .args(["--georeference", "48.8566,2.3522,10", "--export", "kml"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = without_lines(&String::from_utf8(output.stdout).expect("Found invalid UTF-8"), "<name>");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_kml.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_kml.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_csv() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\input.txt", "--format", "csv"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input.txt", "--format", "csv"])
+      .output()
+      .expect("failed to execute process")
+  };
+  // The first column holds the random ids of the rovers.
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  let stdout = stdout.lines().map(|line| format!("{}\n", line.split_once(',').unwrap().1)).collect::<String>();
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_csv.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_csv.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_mission() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\mission.yaml"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/mission.yaml"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_mission.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_mission.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_lenient() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\input_lenient.txt"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input_lenient.txt"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_terrain.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_terrain.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_sections() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", ".\\tests\\input_sections.txt"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input_sections.txt"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stdout = String::from_utf8(output.stdout).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_sections.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_sections.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_diagnostics() {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("cargo")
+      .args(["run", "--release", "--quiet", "--", "--ephemeral", "--input", ".\\tests\\input_invalid.txt"])
+      .output()
+      .expect("failed to execute process")
+  } else {
+    Command::new("cargo")
+      .args(["run", "--release", "--quiet", "--", "--ephemeral", "--input", "./tests/input_invalid.txt"])
+      .output()
+      .expect("failed to execute process")
+  };
+  let stderr = String::from_utf8(output.stderr).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stderr, read_to_string(".\\tests\\output_invalid.txt").unwrap());
+  } else {
+    assert_eq!(stderr, read_to_string("./tests/output_invalid.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_subcommands() {
+  remove_database("gcs_test_subcommands.sqlite");
+  let plateau: serde_json::Value =
+    serde_json::from_str(&gcs_cli_on("gcs_test_subcommands.sqlite", &["plateau", "create", "5", "5", "--format", "json"])).unwrap();
+  let plateau_id = plateau[0]["id"].as_str().unwrap();
+  gcs_cli_on(
+    "gcs_test_subcommands.sqlite",
+    &["rover", "create", plateau_id, "1", "2", "N", "--name", "spirit"],
+  );
+  assert_eq!(gcs_cli_on("gcs_test_subcommands.sqlite", &["rover", "create", plateau_id, "1", "2", "E"]), "");
+  assert_eq!(
+    gcs_cli_on("gcs_test_subcommands.sqlite", &["rover", "move", plateau_id, "spirit", "LMLMLMLMM"]),
+    "spirit: 1 3 N\n"
+  );
+  assert_eq!(
+    gcs_cli_on("gcs_test_subcommands.sqlite", &["rover", "show", plateau_id, "spirit"]),
+    "Rover spirit is at 1 3 N\n"
+  );
+  assert_eq!(
+    gcs_cli_on("gcs_test_subcommands.sqlite", &["plateau", "delete", plateau_id]),
+    format!("Deleted plateau '{}' and its rover.\n", plateau_id)
+  );
+}
+
+#[test]
+fn test_ambiguous_path() {
+  remove_database("gcs_test_ambiguous.sqlite");
+  gcs_cli_on("gcs_test_ambiguous.sqlite", &["plateau", "create", "5", "5", "--name", "ambiguous"]);
+  gcs_cli_on(
+    "gcs_test_ambiguous.sqlite",
+    &["rover", "create", "ambiguous", "1", "2", "N", "--name", "spirit"],
+  );
+  let input = if cfg!(target_os = "windows") {
+    ".\\tests\\input_ambiguous.txt"
+  } else {
+    "./tests/input_ambiguous.txt"
+  };
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--quiet", "--", "--plateau", "ambiguous", "--input", input])
+    .env("DATABASE_URL", std::env::temp_dir().join("gcs_test_ambiguous.sqlite"))
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  let stderr = String::from_utf8(output.stderr).expect("Found invalid UTF-8");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stderr, read_to_string(".\\tests\\output_ambiguous.txt").unwrap());
+  } else {
+    assert_eq!(stderr, read_to_string("./tests/output_ambiguous.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_bounds_too_large() {
+  let input = std::env::temp_dir().join("gcs_test_bounds.txt");
+  std::fs::write(&input, "5000 5\n1 2 N\nM\n").unwrap();
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--quiet", "--", "--ephemeral", "--input", input.to_str().unwrap()])
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr)
+    .expect("Found invalid UTF-8")
+    .starts_with("error: Expected at most 1000 for the x_max of the plateau but found 5000\n"));
+}
+
+#[test]
+fn test_repl() {
+  remove_database("gcs_test_repl.sqlite");
+  let input = if cfg!(target_os = "windows") {
+    read_to_string(".\\tests\\input_repl.txt").unwrap()
+  } else {
+    read_to_string("./tests/input_repl.txt").unwrap()
+  };
+  let mut child = Command::new("cargo")
+    .args(["run", "--release", "--", "repl"])
+    .env("DATABASE_URL", std::env::temp_dir().join("gcs_test_repl.sqlite"))
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()
+    .expect("failed to execute process");
+  child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+  let output = child.wait_with_output().expect("failed to execute process");
+  let stdout = without_lines(&String::from_utf8(output.stdout).expect("Found invalid UTF-8"), "Created plateau");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_repl.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_repl.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_repl_in_memory() {
+  let input = if cfg!(target_os = "windows") {
+    read_to_string(".\\tests\\input_repl.txt").unwrap()
+  } else {
+    read_to_string("./tests/input_repl.txt").unwrap()
+  };
+  let mut child = Command::new("cargo")
+    .args(["run", "--release", "--", "repl"])
+    .env("DATABASE_URL", ":memory:")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()
+    .expect("failed to execute process");
+  child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+  let output = child.wait_with_output().expect("failed to execute process");
+  let stdout = without_lines(&String::from_utf8(output.stdout).expect("Found invalid UTF-8"), "Created plateau");
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_repl.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_repl.txt").unwrap());
+  }
+}
+
+#[test]
+fn test_tui_without_terminal() {
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--quiet", "--", "tui"])
+    .env("DATABASE_URL", ":memory:")
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr)
+    .expect("Found invalid UTF-8")
+    .starts_with("The dashboard needs a terminal"));
+}
+
+#[test]
+fn test_server_without_database() {
+  let args = ["run", "--release", "--quiet", "--", "--server", "http://localhost:9090", "db", "info"];
+  let output = Command::new("cargo").args(args).output().expect("failed to execute process");
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr)
+    .expect("Found invalid UTF-8")
+    .starts_with("This command opens the database, it can't be used with --server"));
+}
+
+#[test]
+fn test_ephemeral_without_database() {
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--", "--ephemeral", "--input", "./tests/input.txt"])
+    .env("DATABASE_URL", "./missing/gcs.sqlite")
+    .output()
+    .expect("failed to execute process");
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stdout).expect("Found invalid UTF-8"),
+    read_to_string("./tests/output.txt").unwrap()
+  );
+}
+
+#[test]
+fn test_postgres_without_feature() {
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--quiet", "--", "--list-plateaus"])
+    .env("DATABASE_URL", "postgres://localhost/gcs")
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  let stderr = String::from_utf8(output.stderr).expect("Found invalid UTF-8");
+  assert!(stderr.starts_with("Failed to open the database: "));
+  assert!(stderr.contains("DATABASE_URL names a PostgreSQL database, build with the postgres feature to use it"));
+  assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_missing_database_url() {
+  // Away from the .env file of the repository, nothing sets DATABASE_URL.
+  let manifest = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+  let output = Command::new("cargo")
+    .args([
+      "run",
+      "--release",
+      "--quiet",
+      "--manifest-path",
+      manifest.to_str().unwrap(),
+      "--",
+      "plateau",
+      "list",
+    ])
+    .current_dir(std::env::temp_dir())
+    .env_remove("DATABASE_URL")
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  let stderr = String::from_utf8(output.stderr).expect("Found invalid UTF-8");
+  assert!(stderr.starts_with("DATABASE_URL must be set, or --server or --ephemeral given: "));
+  assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_fresh_database() {
+  let database = std::env::temp_dir().join("gcs_test_fresh_database.sqlite");
+  remove_database("gcs_test_fresh_database.sqlite");
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--", "--input", "./tests/input.txt"])
+    .env("DATABASE_URL", &database)
+    .output()
+    .expect("failed to execute process");
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stdout).expect("Found invalid UTF-8"),
+    read_to_string("./tests/output.txt").unwrap()
+  );
+}
+
+/// Runs `gcs_cli` on a database of its own in the temporary directory, created anew by the first call of a test.
+fn gcs_cli_on(database: &str, args: &[&str]) -> String {
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--"])
+    .args(args)
+    .env("DATABASE_URL", std::env::temp_dir().join(database))
+    .output()
+    .expect("failed to execute process");
+  String::from_utf8(output.stdout).expect("Found invalid UTF-8")
+}
+
+fn remove_database(database: &str) {
+  for suffix in ["", "-wal", "-shm"] {
+    let _ = std::fs::remove_file(std::env::temp_dir().join(format!("{}{}", database, suffix)));
+  }
+}
+
+#[test]
+fn test_backup_and_restore() {
+  let backup = std::env::temp_dir().join("gcs_test_backup.bak.sqlite");
+  let backup = backup.to_str().unwrap();
+  remove_database("gcs_test_backup.sqlite");
+  gcs_cli_on("gcs_test_backup.sqlite", &["--input", "./tests/input.txt"]);
+  assert_eq!(
+    gcs_cli_on("gcs_test_backup.sqlite", &["db", "backup", backup]),
+    format!("Backed up the database to {}\n", backup)
+  );
+  gcs_cli_on("gcs_test_backup.sqlite", &["--input", "./tests/input.txt"]);
+  assert!(gcs_cli_on("gcs_test_backup.sqlite", &["--format", "csv", "db", "info"])
+    .lines()
+    .nth(1)
+    .unwrap()
+    .starts_with("2,4,8,"));
+  assert_eq!(
+    gcs_cli_on("gcs_test_backup.sqlite", &["db", "restore", backup]),
+    format!("Restored the database from {}\n", backup)
+  );
+  assert!(gcs_cli_on("gcs_test_backup.sqlite", &["--format", "csv", "db", "info"])
+    .lines()
+    .nth(1)
+    .unwrap()
+    .starts_with("1,2,4,"));
+}
+
+#[test]
+fn test_snapshot() {
+  let snapshot = std::env::temp_dir().join("gcs_test_snapshot.json");
+  let snapshot = snapshot.to_str().unwrap();
+  remove_database("gcs_test_snapshot.sqlite");
+  remove_database("gcs_test_snapshot_imported.sqlite");
+  gcs_cli_on("gcs_test_snapshot.sqlite", &["--input", "./tests/input.txt"]);
+  assert_eq!(
+    gcs_cli_on("gcs_test_snapshot.sqlite", &["db", "export", snapshot]),
+    format!("Exported 1 plateaus, 2 rovers and 4 history entries to {}\n", snapshot)
+  );
+  // Importing again replaces the plateau.
+  for _ in 0..2 {
+    assert_eq!(
+      gcs_cli_on("gcs_test_snapshot_imported.sqlite", &["db", "import", snapshot]),
+      "Imported 1 plateaus, 2 rovers and 4 history entries\n"
+    );
+  }
+  assert!(gcs_cli_on("gcs_test_snapshot_imported.sqlite", &["--format", "csv", "db", "info"])
+    .lines()
+    .nth(1)
+    .unwrap()
+    .starts_with("1,2,4,"));
+}
+
+#[test]
+fn test_invalid_snapshot() {
+  let snapshot = std::env::temp_dir().join("gcs_test_invalid_snapshot.json");
+  let snapshot = snapshot.to_str().unwrap();
+  remove_database("gcs_test_invalid_snapshot.sqlite");
+  remove_database("gcs_test_invalid_snapshot_imported.sqlite");
+  gcs_cli_on("gcs_test_invalid_snapshot.sqlite", &["--input", "./tests/input.txt"]);
+  gcs_cli_on("gcs_test_invalid_snapshot.sqlite", &["db", "export", snapshot]);
+  let exported: serde_json::Value = serde_json::from_str(&read_to_string(snapshot).unwrap()).unwrap();
+  let import = |database: &str, json: &serde_json::Value| {
+    std::fs::write(snapshot, json.to_string()).unwrap();
+    let output = Command::new("cargo")
+      .args(["run", "--release", "--quiet", "--", "db", "import", snapshot])
+      .env("DATABASE_URL", std::env::temp_dir().join(database))
+      .output()
+      .expect("failed to execute process");
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).expect("Found invalid UTF-8")
+  };
+
+  // A rover off its plateau.
+  let mut outside = exported.clone();
+  outside["plateaus"][0]["rovers"][0]["x"] = serde_json::json!(99);
+  assert!(import("gcs_test_invalid_snapshot_imported.sqlite", &outside).starts_with("Failed to import the snapshot: Error: SnapshotParseError(\"Plateau "));
+  // The same rovers under another plateau of the database.
+  let mut moved = exported.clone();
+  moved["plateaus"][0]["id"] = serde_json::json!("00000000-0000-4000-8000-000000000000");
+  assert!(import("gcs_test_invalid_snapshot.sqlite", &moved).contains("already belongs to plateau"));
+  // Nothing was imported.
+  assert!(gcs_cli_on("gcs_test_invalid_snapshot_imported.sqlite", &["--format", "csv", "db", "info"])
+    .lines()
+    .nth(1)
+    .unwrap()
+    .starts_with("0,0,0,"));
+}
+
+#[test]
+fn test_replay() {
+  remove_database("gcs_test_replay.sqlite");
+  gcs_cli_on("gcs_test_replay.sqlite", &["plateau", "create", "3", "2", "--name", "replay"]);
+  gcs_cli_on("gcs_test_replay.sqlite", &["rover", "create", "replay", "0", "0", "N", "--name", "spirit"]);
+  gcs_cli_on("gcs_test_replay.sqlite", &["rover", "create", "replay", "3", "2", "S", "--name", "opportunity"]);
+  gcs_cli_on("gcs_test_replay.sqlite", &["rover", "move", "replay", "spirit", "MRM"]);
+  gcs_cli_on("gcs_test_replay.sqlite", &["rover", "move", "replay", "opportunity", "MM"]);
+  let stdout = gcs_cli_on("gcs_test_replay.sqlite", &["replay", "replay", "--frames"]);
+  if cfg!(target_os = "windows") {
+    assert_eq!(stdout, read_to_string(".\\tests\\output_replay.txt").unwrap());
+  } else {
+    assert_eq!(stdout, read_to_string("./tests/output_replay.txt").unwrap());
+  }
+  let output = Command::new("cargo")
+    .args(["run", "--release", "--quiet", "--", "replay", "replay", "--frame-duration", "0"])
+    .env("DATABASE_URL", std::env::temp_dir().join("gcs_test_replay.sqlite"))
+    .output()
+    .expect("failed to execute process");
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr)
+    .expect("Found invalid UTF-8")
+    .contains("Expected a positive frame duration but found 0"));
+}